@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     io::{BufReader, Cursor},
     sync::{mpsc, OnceLock},
     thread,
@@ -6,17 +7,45 @@ use std::{
 };
 
 use anyhow::{ensure, Result};
+use clap::ValueEnum;
 use rodio::{Decoder, DeviceSinkBuilder, Player};
 use tokio::sync::{broadcast, oneshot};
 
+use crate::config::settings::current_config;
+
 const JOIN_SOUND_BYTES: &[u8] = include_bytes!("../../sounds/mixkit-correct-answer-tone-2870.wav");
 const LEAVE_SOUND_BYTES: &[u8] =
     include_bytes!("../../sounds/mixkit-software-interface-back-2575.wav");
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, ValueEnum)]
 pub enum AudioEvent {
     Join,
     Leave,
+    FriendJoin,
+    FriendLeave,
+}
+
+/// friends get the normal sounds pitched up unless they have their own
+const FRIEND_SPEED: f32 = 1.25;
+
+/// sound bytes and playback speed
+fn get_sound(event: &AudioEvent) -> (Cow<'static, [u8]>, f32) {
+    let config = current_config();
+    let (path, default_bytes) = match event {
+        AudioEvent::Join => return (Cow::Borrowed(JOIN_SOUND_BYTES), 1.0),
+        AudioEvent::Leave => return (Cow::Borrowed(LEAVE_SOUND_BYTES), 1.0),
+        AudioEvent::FriendJoin => (&config.friends.join_sound, JOIN_SOUND_BYTES),
+        AudioEvent::FriendLeave => (&config.friends.leave_sound, LEAVE_SOUND_BYTES),
+    };
+
+    match path.as_ref().map(std::fs::read) {
+        Some(Ok(bytes)) => (Cow::Owned(bytes), 1.0),
+        Some(Err(e)) => {
+            eprintln!("start_audio: reading {path:?}: {e}");
+            (Cow::Borrowed(default_bytes), FRIEND_SPEED)
+        }
+        None => (Cow::Borrowed(default_bytes), FRIEND_SPEED),
+    }
 }
 
 static EVENTS_TX: OnceLock<mpsc::Sender<AudioEvent>> = OnceLock::new();
@@ -63,12 +92,20 @@ pub async fn start_audio(shutdown_send: broadcast::Sender<()>) -> Result<()> {
 
                 let stream = DeviceSinkBuilder::open_default_sink()?;
 
+                let (bytes, speed) = get_sound(&event);
+                let decoder = match Decoder::new(BufReader::new(Cursor::new(bytes))) {
+                    Ok(decoder) => decoder,
+                    Err(e) => {
+                        // a configured file that isn't a wav
+                        eprintln!("start_audio: {e}");
+                        continue;
+                    }
+                };
+
                 let sink = Player::connect_new(stream.mixer());
-                sink.set_volume(0.2);
-                sink.append(Decoder::new(BufReader::new(Cursor::new(match event {
-                    AudioEvent::Join => JOIN_SOUND_BYTES,
-                    AudioEvent::Leave => LEAVE_SOUND_BYTES,
-                })))?);
+                sink.set_volume(current_config().audio.volume);
+                sink.set_speed(speed);
+                sink.append(decoder);
                 sink.sleep_until_end();
 
                 drop(stream);
@@ -91,6 +128,10 @@ pub async fn start_audio(shutdown_send: broadcast::Sender<()>) -> Result<()> {
 }
 
 pub fn handle_event(event: &AudioEvent) -> Result<()> {
+    if !current_config().audio.enabled {
+        return Ok(());
+    }
+
     EVENTS_TX
         .get()
         .ok_or_else(|| anyhow::anyhow!("Audio events channel not initialized"))?
@@ -98,3 +139,28 @@ pub fn handle_event(event: &AudioEvent) -> Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to send audio event: {e}"))?;
     Ok(())
 }
+
+/// Whether the default output device opens
+pub fn check_audio_device() -> Result<()> {
+    DeviceSinkBuilder::open_default_sink()?;
+    Ok(())
+}
+
+/// Plays one sound, waiting for it to finish.
+pub async fn test_sound(event: &AudioEvent) -> Result<()> {
+    ensure!(
+        current_config().audio.enabled,
+        "audio is turned off in the config"
+    );
+
+    let (shutdown_send, mut shutdown_recv) = broadcast::channel(8);
+    start_audio(shutdown_send.clone()).await?;
+    handle_event(event)?;
+
+    // only noticed once the sound is done
+    let _ = shutdown_send.send(());
+    drop(shutdown_send);
+    while shutdown_recv.recv().await.is_ok() {}
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::ConfigCommand;
+use crate::config::settings::{check_config, Config};
+
+/// `config_path` is `--config` or the default
+pub async fn config_command(command: ConfigCommand, config_path: &Path) -> Result<()> {
+    match command {
+        ConfigCommand::Check { path } => {
+            check_config(path.as_deref().unwrap_or(config_path)).await?;
+        }
+        ConfigCommand::Path => println!("{}", config_path.display()),
+        ConfigCommand::Default => print!("{}", toml::to_string_pretty(&Config::default())?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use time::{macros::format_description, Duration, PrimitiveDateTime};
+
+use super::HistoryCommand;
+use crate::{
+    history::{start_history, with_history, History, HistoryKind, HistoryRecord},
+    notifier::now_local,
+    parser::{event::Player, world::WorldInstance},
+};
+
+pub async fn history_command(command: HistoryCommand) -> Result<()> {
+    start_history().await?;
+
+    with_history(|history| match command {
+        HistoryCommand::Seen { player } => seen(history, &player),
+        HistoryCommand::Recent { since } => recent(history, since),
+        HistoryCommand::Instance { instance } => in_instance(history, &instance),
+    })
+    .context("history isn't open")?
+}
+
+fn seen(history: &History, user_id_or_name: &str) -> Result<()> {
+    let Some(last) = history.last_seen(user_id_or_name)? else {
+        println!("never seen {user_id_or_name}");
+        return Ok(());
+    };
+    let encounters = history.encounters_before(&last.player, now_local())?;
+
+    println!("{}", describe_player(&last.player));
+    println!("last seen {}", describe_record(&last));
+    println!(
+        "joined {} time{}",
+        encounters.joins,
+        if encounters.joins == 1 { "" } else { "s" }
+    );
+
+    if let Some(user_id) = &last.player.user_id {
+        for name_change in history.name_changes(user_id)? {
+            println!(
+                "{} renamed from {} to {}",
+                format_timestamp(name_change.timestamp),
+                name_change.old_name,
+                name_change.new_name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn recent(history: &History, since: Duration) -> Result<()> {
+    let to = now_local();
+    let records = history.between(to - since, to)?;
+    if records.is_empty() {
+        println!("nothing in the history since then");
+    }
+
+    let mut world = None;
+    for record in records {
+        if world.as_ref() != Some(&record.world) {
+            match &record.world {
+                Some(instance) => println!("{}", describe_instance(instance)),
+                None => println!("unknown instance"),
+            }
+            world = Some(record.world.clone());
+        }
+
+        print_record(&record);
+    }
+
+    Ok(())
+}
+
+fn in_instance(history: &History, instance: &WorldInstance) -> Result<()> {
+    let records = history.in_instance(instance)?;
+    // the name is only known from the log
+    let Some(instance) = records.first().and_then(|record| record.world.as_ref()) else {
+        println!(
+            "nothing in the history for {}:{}",
+            instance.world_id, instance.instance_id
+        );
+        return Ok(());
+    };
+
+    println!("{}", describe_instance(instance));
+    for record in &records {
+        print_record(record);
+    }
+
+    Ok(())
+}
+
+/// an indented line under the instance
+fn print_record(record: &HistoryRecord) {
+    println!(
+        "    {} {} {}",
+        format_timestamp(record.timestamp),
+        describe_player(&record.player),
+        verb(record.kind)
+    );
+}
+
+/// `World wrld_...:12345~region(eu)`
+fn describe_instance(instance: &WorldInstance) -> String {
+    format!("{instance} {}:{}", instance.world_id, instance.instance_id)
+}
+
+fn describe_player(player: &Player) -> String {
+    match &player.user_id {
+        Some(user_id) => format!("{} ({user_id})", player.display_name),
+        None => player.display_name.clone(),
+    }
+}
+
+/// `2024-01-01 20:00:00 in World (left)`
+fn describe_record(record: &HistoryRecord) -> String {
+    let mut text = format_timestamp(record.timestamp);
+    if let Some(world) = &record.world {
+        let _ = write!(text, " in {world}");
+    }
+    let _ = write!(text, " ({})", verb(record.kind));
+    text
+}
+
+fn verb(kind: HistoryKind) -> &'static str {
+    match kind {
+        HistoryKind::Join => "joined",
+        HistoryKind::Leave => "left",
+    }
+}
+
+fn format_timestamp(timestamp: PrimitiveDateTime) -> String {
+    timestamp
+        .format(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second]"
+        ))
+        .unwrap_or_default()
+}
@@ -0,0 +1,36 @@
+use anyhow::{bail, Result};
+use time::OffsetDateTime;
+
+use super::IgnoreCommand;
+use crate::config::ignore::IgnoreList;
+
+pub async fn ignore_command(command: IgnoreCommand) -> Result<()> {
+    let mut ignore_list = IgnoreList::load().await?;
+    match command {
+        IgnoreCommand::Add { user_id, duration } => {
+            let until = duration.map(|duration| OffsetDateTime::now_utc() + duration);
+            ignore_list.add(&user_id, until)?;
+            ignore_list.save().await?;
+            println!("ignoring {user_id}");
+        }
+        IgnoreCommand::Remove { user_id } => {
+            if !ignore_list.remove(&user_id) {
+                bail!("{user_id} wasn't ignored");
+            }
+            ignore_list.save().await?;
+            println!("no longer ignoring {user_id}");
+        }
+        IgnoreCommand::List => {
+            let now = OffsetDateTime::now_utc();
+            for entry in ignore_list
+                .entries
+                .iter()
+                .filter(|entry| entry.is_active(now))
+            {
+                println!("{entry}");
+            }
+        }
+    }
+
+    Ok(())
+}
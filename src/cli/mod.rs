@@ -0,0 +1,166 @@
+// the doc comments are the --help text, so no markdown
+#![allow(clippy::doc_markdown)]
+
+pub mod config;
+pub mod history;
+pub mod ignore;
+pub mod status;
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use time::Duration;
+
+use crate::{
+    audio::AudioEvent, config::ignore::parse_duration, notifier::sinks::SinkType,
+    parser::world::WorldInstance,
+};
+
+/// Notifications in SteamVR when players join or leave your VRChat instance
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// config file to use instead of the one in the config directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// where VRChat writes its logs, overrides vrchat_dir
+    #[arg(long, global = true, env = "VRCHAT_LOG_DIR", value_name = "DIR")]
+    pub log_dir: Option<PathBuf>,
+
+    /// only send notifications to these, overrides the configured sinks
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "OUTPUT"
+    )]
+    pub outputs: Option<Vec<SinkType>>,
+
+    /// print each event read from the log, twice to also print lines that
+    /// couldn't be handled
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// for run without the subcommand
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Watch the VRChat log and notify on joins and leaves, the default
+    Run(RunArgs),
+    /// Register with SteamVR and start along with it
+    Install,
+    /// Unregister from SteamVR and delete the files install copied
+    Uninstall,
+    /// Run an existing log through the parser, printing the notifications it
+    /// would have sent
+    Replay {
+        log_path: PathBuf,
+        /// follow the original timing, 2.0 being twice as fast, instead of
+        /// running as fast as possible
+        #[arg(long, value_name = "MULTIPLIER")]
+        speed: Option<f64>,
+    },
+    /// Check everything notifications depend on, printing pass or fail for
+    /// each
+    #[command(visible_alias = "doctor")]
+    Status,
+    /// Look up earlier joins and leaves
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Check or print the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Players whose joins and leaves make no toast or sound
+    #[command(subcommand)]
+    Ignore(IgnoreCommand),
+    /// Send a notification to the enabled outputs
+    TestNotify {
+        #[arg(default_value = "test notification")]
+        text: String,
+    },
+    /// Play one of the sounds
+    TestSound {
+        #[arg(value_enum, default_value_t = AudioEvent::Join)]
+        sound: AudioEvent,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+pub struct RunArgs {
+    /// keep running without SteamVR, showing desktop notifications instead
+    #[arg(long)]
+    pub desktop: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// When someone was last seen, how often they joined and their earlier
+    /// names
+    Seen {
+        /// usr_... or a display name
+        player: String,
+    },
+    /// Joins and leaves in the last while, by instance
+    Recent {
+        /// 30m, 12h, 7d
+        #[arg(long, default_value = "12h", value_parser = parse_duration)]
+        since: Duration,
+    },
+    /// Everyone who joined or left one instance
+    Instance {
+        /// wrld_...:12345~region(eu), as printed by recent
+        instance: WorldInstance,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Load the config and find the log directory with it
+    Check {
+        /// instead of --config or the default path
+        path: Option<PathBuf>,
+    },
+    /// Print where the config file is
+    Path,
+    /// Print the default config
+    Default,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum IgnoreCommand {
+    Add {
+        /// usr_...
+        user_id: String,
+        /// 30m, 12h, 7d, forever if unset
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Option<Duration>,
+    },
+    Remove {
+        /// usr_...
+        user_id: String,
+    },
+    List,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// how many times --verbose was given
+#[must_use]
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
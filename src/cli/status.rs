@@ -0,0 +1,122 @@
+use std::{path::Path, time::SystemTime};
+
+use anyhow::{bail, ensure, Context, Result};
+use tokio::fs;
+
+use crate::{
+    audio::check_audio_device,
+    config::{
+        settings::{current_config, set_config, Config},
+        setup_vr::{check_installed_exe, get_manifest_path},
+    },
+    notifier::enrich::ago,
+    parser::utils::{get_newest_log_path, get_vrchat_dir},
+    vr::setup::check_installed,
+};
+
+/// One line of the report, with what was found or what's wrong
+struct Check {
+    name: &'static str,
+    result: Result<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String>) -> Self {
+        Self { name, result }
+    }
+}
+
+/// Checks everything notifications depend on, printing pass or fail for
+/// each. Fails if any did.
+pub async fn status(config_path: &Path) -> Result<()> {
+    // first, the others use the config
+    let checks = vec![
+        Check::new("config", check_config_file(config_path).await),
+        Check::new(
+            "log directory",
+            get_vrchat_dir()
+                .await
+                .map(|vrchat_dir| vrchat_dir.display().to_string()),
+        ),
+        Check::new("newest log", check_newest_log().await),
+        Check::new("outputs", check_outputs()),
+        Check::new("manifest", check_manifest().await),
+        Check::new(
+            "SteamVR",
+            check_installed().map(|()| "installed, auto-launch on".to_string()),
+        ),
+        Check::new(
+            "installed exe",
+            check_installed_exe()
+                .await
+                .map(|exe_path| format!("{}, same as running", exe_path.display())),
+        ),
+        Check::new(
+            "audio",
+            check_audio_device().map(|()| "default output device opens".to_string()),
+        ),
+    ];
+
+    let mut failed = 0;
+    for check in &checks {
+        match &check.result {
+            Ok(found) => println!("pass  {}: {found}", check.name),
+            Err(e) => {
+                failed += 1;
+                println!("FAIL  {}: {e:#}", check.name);
+            }
+        }
+    }
+
+    ensure!(failed == 0, "{failed} of {} checks failed", checks.len());
+    println!("all checks passed");
+
+    Ok(())
+}
+
+async fn check_config_file(config_path: &Path) -> Result<String> {
+    set_config(Config::load(config_path).await?);
+
+    Ok(if config_path.is_file() {
+        config_path.display().to_string()
+    } else {
+        format!("{} doesn't exist, using defaults", config_path.display())
+    })
+}
+
+async fn check_newest_log() -> Result<String> {
+    let log_path = get_newest_log_path()
+        .await?
+        .context("no output_log_*.txt files, has VRChat been started?")?;
+
+    let modified = fs::metadata(&log_path).await?.modified()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+
+    Ok(format!(
+        "{}, last written {}",
+        log_path.display(),
+        ago(age.try_into()?)
+    ))
+}
+
+fn check_outputs() -> Result<String> {
+    let outputs = current_config()
+        .sinks
+        .iter()
+        .map(|sink| sink.sink_type.to_string())
+        .collect::<Vec<_>>();
+    ensure!(!outputs.is_empty(), "no sinks configured");
+
+    Ok(outputs.join(", "))
+}
+
+async fn check_manifest() -> Result<String> {
+    let manifest_path = get_manifest_path().await?;
+    if !manifest_path.is_file() {
+        bail!("{} doesn't exist, run install", manifest_path.display());
+    }
+
+    Ok(manifest_path.display().to_string())
+}
@@ -0,0 +1,204 @@
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::{macros::format_description, Duration, OffsetDateTime, UtcOffset};
+use tokio::fs;
+
+use super::utils::get_data_dir;
+use crate::parser::event::Player;
+
+const IGNORE_FILE_NAME: &str = "ignored.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoreEntry {
+    /// `usr_...`
+    pub user_id: String,
+    /// `None` for forever
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+}
+
+impl IgnoreEntry {
+    #[must_use]
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+impl Display for IgnoreEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.until {
+            Some(until) => {
+                let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+                let until = until
+                    .to_offset(offset)
+                    .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                    .map_err(|_| std::fmt::Error)?;
+                write!(f, "{} until {until}", self.user_id)
+            }
+            None => write!(f, "{}", self.user_id),
+        }
+    }
+}
+
+/// Players whose joins and leaves make no toast or sound
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoreList {
+    pub entries: Vec<IgnoreEntry>,
+}
+
+impl IgnoreList {
+    async fn path() -> Result<PathBuf> {
+        Ok(get_data_dir().await?.join(IGNORE_FILE_NAME))
+    }
+
+    pub async fn load() -> Result<Self> {
+        let path = Self::path().await?;
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let bytes = fs::read(&path).await?;
+        serde_json::from_slice(&bytes).with_context(|| format!("loading {path:?}"))
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::path().await?;
+        let bytes = serde_json::to_vec_pretty(self)?;
+
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, bytes).await?;
+        fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+
+    /// replaces any existing entry for `user_id`, and drops expired ones
+    pub fn add(&mut self, user_id: &str, until: Option<OffsetDateTime>) -> Result<()> {
+        ensure!(user_id.starts_with("usr_"), "{user_id:?} isn't a usr_ id");
+
+        let now = OffsetDateTime::now_utc();
+        self.entries
+            .retain(|entry| entry.user_id != user_id && entry.is_active(now));
+        self.entries.push(IgnoreEntry {
+            user_id: user_id.to_string(),
+            until,
+        });
+
+        Ok(())
+    }
+
+    /// `false` if it wasn't there
+    pub fn remove(&mut self, user_id: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.user_id != user_id);
+        self.entries.len() != len
+    }
+
+    #[must_use]
+    pub fn contains(&self, player: &Player, now: OffsetDateTime) -> bool {
+        player.user_id.as_ref().is_some_and(|user_id| {
+            self.entries
+                .iter()
+                .any(|entry| &entry.user_id == user_id && entry.is_active(now))
+        })
+    }
+}
+
+struct LoadedIgnoreList {
+    /// the file's modified time when it was loaded
+    modified: Option<SystemTime>,
+    list: Arc<IgnoreList>,
+}
+
+static IGNORE_LIST: LazyLock<Mutex<Option<LoadedIgnoreList>>> = LazyLock::new(Mutex::default);
+
+/// Reloads the list if the file changed, so the cli can edit it while we're
+/// running.
+async fn current_ignore_list() -> Result<Arc<IgnoreList>> {
+    let modified = fs::metadata(IgnoreList::path().await?)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    if let Some(loaded) = IGNORE_LIST.lock().unwrap().as_ref() {
+        if loaded.modified == modified {
+            return Ok(loaded.list.clone());
+        }
+    }
+
+    let list = Arc::new(IgnoreList::load().await?);
+    *IGNORE_LIST.lock().unwrap() = Some(LoadedIgnoreList {
+        modified,
+        list: list.clone(),
+    });
+    Ok(list)
+}
+
+pub async fn is_ignored(player: &Player) -> bool {
+    if player.user_id.is_none() {
+        return false;
+    }
+
+    match current_ignore_list().await {
+        Ok(list) => list.contains(player, OffsetDateTime::now_utc()),
+        Err(e) => {
+            eprintln!("loading ignore list: {e:?}");
+            false
+        }
+    }
+}
+
+/// `30m`, `12h`, `7d`
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let split = s.len().saturating_sub(1);
+    let (number, unit) = (s.get(..split).unwrap_or(""), s.get(split..).unwrap_or(""));
+    let number = number
+        .parse::<u32>()
+        .with_context(|| format!("bad duration {s:?}, expected something like 30m, 12h or 7d"))?;
+
+    let duration = match unit {
+        "m" => Duration::minutes(number.into()),
+        "h" => Duration::hours(number.into()),
+        "d" => Duration::days(number.into()),
+        _ => bail!("bad duration unit in {s:?}, expected m, h or d"),
+    };
+
+    Ok(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::{parse_duration, IgnoreList};
+    use crate::parser::event::Player;
+
+    #[test]
+    fn ignores_until_expiry() {
+        let now = datetime!(2024-01-01 20:00 UTC);
+        let player = Player::parse("SpiralP (usr_1)");
+
+        let mut list = IgnoreList::default();
+        assert!(list.add("SpiralP", None).is_err());
+        list.add("usr_1", Some(now + Duration::hours(1))).unwrap();
+        assert!(list.contains(&player, now));
+        assert!(!list.contains(&player, now + Duration::hours(2)));
+        assert!(!list.contains(&Player::parse("SpiralP"), now));
+
+        assert!(list.remove("usr_1"));
+        assert!(!list.remove("usr_1"));
+        assert!(!list.contains(&player, now));
+
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod ignore;
+pub mod reload;
+pub mod resume;
+pub mod settings;
 pub mod setup_vr;
 pub mod utils;
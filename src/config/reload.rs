@@ -0,0 +1,108 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use tokio::{fs, sync::broadcast, time::MissedTickBehavior};
+
+use super::settings::{current_config, set_config, Config};
+use crate::{
+    log_watcher::fs_events::FsEvents,
+    notifier::{
+        send_notification,
+        sinks::{register_configured_sinks, unregister_configured_sinks},
+        Notification, NotificationKind,
+    },
+};
+
+/// Switch to `config`, re-registering the sinks. Everything else reads the
+/// config when it needs it.
+pub fn apply_config(config: Config) {
+    let old = current_config();
+
+    if old.vrchat_dir != config.vrchat_dir
+        || old.desktop != config.desktop
+        || old.sources != config.sources
+    {
+        println!("vrchat_dir, desktop and sources only change after a restart");
+    }
+
+    unregister_configured_sinks(&old.sinks);
+    register_configured_sinks(&config.sinks);
+    set_config(config);
+}
+
+async fn read_config_text(path: &Path) -> Option<String> {
+    fs::read_to_string(path).await.ok()
+}
+
+/// Reloads the config file when it changes. An invalid file keeps the
+/// previous config and shows the error instead.
+pub async fn start_config_watcher(
+    shutdown_send: broadcast::Sender<()>,
+    path: PathBuf,
+) -> Result<()> {
+    let mut shutdown_recv = shutdown_send.subscribe();
+
+    // editors often replace the file instead of writing to it, so watch the
+    // directory
+    let config_dir = path.parent().context("config path has no parent")?;
+    let mut fs_events = FsEvents::modified(config_dir);
+    let mut last_text = read_config_text(&path).await;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(current_config().poll.log_dir_interval());
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_recv.recv() => {
+                    println!("start_config_watcher got shutdown");
+                    break;
+                },
+                _ = interval.tick() => {},
+                () = fs_events.changed() => {},
+            }
+
+            // give whoever is writing it a moment to finish
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            // other files in the directory change too
+            let text = read_config_text(&path).await;
+            if text == last_text {
+                continue;
+            }
+            last_text = text;
+
+            match Config::load(&path).await {
+                Ok(config) => {
+                    println!("reloaded {}", path.display());
+                    apply_config(config);
+                }
+                Err(e) => {
+                    eprintln!("reloading config: {e:?}");
+                    send_notification(&Notification {
+                        body: e
+                            .root_cause()
+                            .to_string()
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .map(ToString::to_string)
+                            .collect(),
+                        ..Notification::new(
+                            NotificationKind::Status,
+                            "config error, keeping the previous config",
+                        )
+                    });
+                }
+            }
+        }
+
+        println!("start_config_watcher end");
+        drop(shutdown_send);
+    });
+
+    Ok(())
+}
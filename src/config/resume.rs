@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::utils::get_data_dir;
+use crate::{
+    parser::world::WorldInstance,
+    roster::{Roster, RosterEntry},
+};
+
+const RESUME_FILE_NAME: &str = "resume.json";
+
+/// Where we stopped reading the active log, so a restart can pick up from there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeState {
+    pub log_path: PathBuf,
+    pub offset: u64,
+    pub world: Option<WorldInstance>,
+    pub players: Vec<RosterEntry>,
+}
+
+impl ResumeState {
+    #[must_use]
+    pub fn new(log_path: PathBuf, offset: u64, roster: &Roster) -> Self {
+        Self {
+            log_path,
+            offset,
+            world: roster.world().cloned(),
+            players: roster.entries().into_iter().cloned().collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn to_roster(&self) -> Roster {
+        let mut roster = Roster::new();
+        roster.set_world(self.world.clone());
+        for entry in &self.players {
+            roster.join(entry.player.clone(), entry.joined_at);
+        }
+        roster
+    }
+
+    /// `resume-{source}.json` for log sources other than the main one
+    async fn path(source: Option<&str>) -> Result<PathBuf> {
+        let file_name = match source {
+            Some(source) => format!("resume-{source}.json"),
+            None => RESUME_FILE_NAME.to_string(),
+        };
+        Ok(get_data_dir().await?.join(file_name))
+    }
+
+    pub async fn load(source: Option<&str>) -> Result<Option<Self>> {
+        let path = Self::path(source).await?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&path).await?;
+        Ok(Some(serde_json::from_slice(&bytes)?))
+    }
+
+    pub async fn save(&self, source: Option<&str>) -> Result<()> {
+        let path = Self::path(source).await?;
+        let bytes = serde_json::to_vec_pretty(self)?;
+
+        // write then rename so a crash mid-write doesn't leave a broken file
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, bytes).await?;
+        fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, OnceLock, RwLock},
+    time::Duration,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::utils::get_config_dir;
+use crate::{
+    log_watcher::SourceConfig,
+    notifier::{
+        enrich::EnrichOptions,
+        friends::FriendsOptions,
+        grouping::GroupingOptions,
+        sinks::{SinkConfig, SinkType},
+    },
+    parser::utils::get_vrchat_dir,
+    session::SummaryOptions,
+};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// `config.toml` in the config directory, every field is optional
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// where the `output_log_*.txt` files are, found automatically if unset,
+    /// `--log-dir` and `VRCHAT_LOG_DIR` win over this
+    pub vrchat_dir: Option<PathBuf>,
+    /// keep running without `SteamVR`, same as `--desktop`
+    pub desktop: bool,
+    /// joins and leaves closer together than this are shown as one
+    pub debounce_ms: u64,
+    pub audio: AudioConfig,
+    pub grouping: GroupingOptions,
+    pub friends: FriendsOptions,
+    pub enrich: EnrichOptions,
+    pub summary: SummaryOptions,
+    pub poll: PollConfig,
+    pub sinks: Vec<SinkConfig>,
+    /// more logs to watch, their notifications are tagged with their name
+    pub sources: Vec<SourceConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            vrchat_dir: None,
+            desktop: false,
+            debounce_ms: 1000,
+            audio: AudioConfig::default(),
+            grouping: GroupingOptions::default(),
+            friends: FriendsOptions::default(),
+            enrich: EnrichOptions::default(),
+            summary: SummaryOptions::default(),
+            poll: PollConfig::default(),
+            sinks: vec![
+                SinkConfig::new(SinkType::Stdout),
+                SinkConfig::new(SinkType::Steamvr),
+            ],
+            sources: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    pub enabled: bool,
+    /// `0.0` to `1.0`
+    pub volume: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            volume: 0.2,
+        }
+    }
+}
+
+/// Fallback intervals for when fs events are missed or unsupported
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PollConfig {
+    /// reading new lines from the current log
+    pub log_ms: u64,
+    /// looking for a newer log file
+    pub log_dir_ms: u64,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            log_ms: 1000,
+            log_dir_ms: 10_000,
+        }
+    }
+}
+
+impl PollConfig {
+    #[must_use]
+    pub fn log_interval(&self) -> Duration {
+        Duration::from_millis(self.log_ms)
+    }
+
+    #[must_use]
+    pub fn log_dir_interval(&self) -> Duration {
+        Duration::from_millis(self.log_dir_ms)
+    }
+}
+
+impl Config {
+    pub async fn path() -> Result<PathBuf> {
+        Ok(get_config_dir().await?.join(CONFIG_FILE_NAME))
+    }
+
+    /// defaults if the file doesn't exist, with the command line overrides
+    /// applied
+    pub async fn load(path: &Path) -> Result<Self> {
+        let mut config = if path.is_file() {
+            let text = fs::read_to_string(path)
+                .await
+                .with_context(|| format!("reading {path:?}"))?;
+            Self::parse(&text).with_context(|| format!("loading {path:?}"))?
+        } else {
+            Self::default()
+        };
+
+        if let Some(overrides) = OVERRIDES.get() {
+            overrides.apply(&mut config)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the things the types can't, listing every problem at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.debounce_ms > 60_000 {
+            problems.push(format!(
+                "debounce_ms is {}, should be at most 60000",
+                self.debounce_ms
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.audio.volume) {
+            problems.push(format!(
+                "audio.volume is {}, should be from 0.0 to 1.0",
+                self.audio.volume
+            ));
+        }
+
+        if self.grouping.max_names == 0 {
+            problems.push("grouping.max_names should be at least 1".to_string());
+        }
+
+        if let Err(e) = self.friends.validate() {
+            problems.push(format!("friends: {e}"));
+        }
+
+        for (name, ms) in [
+            ("poll.log_ms", self.poll.log_ms),
+            ("poll.log_dir_ms", self.poll.log_dir_ms),
+        ] {
+            if ms < 100 {
+                problems.push(format!("{name} is {ms}, should be at least 100"));
+            }
+        }
+
+        for (index, sink) in self.sinks.iter().enumerate() {
+            if let Err(e) = sink.validate() {
+                problems.push(format!("sinks[{index}] ({}): {e}", sink.sink_type));
+            }
+
+            if sink.sink_type.is_singleton()
+                && self.sinks[..index]
+                    .iter()
+                    .any(|other| other.sink_type == sink.sink_type)
+            {
+                problems.push(format!(
+                    "sinks[{index}]: there can only be one {} sink",
+                    sink.sink_type
+                ));
+            }
+        }
+
+        for (index, source) in self.sources.iter().enumerate() {
+            if let Err(e) = source.validate() {
+                problems.push(format!("sources[{index}]: {e}"));
+            }
+
+            if self.sources[..index]
+                .iter()
+                .any(|other| other.name == source.name)
+            {
+                problems.push(format!(
+                    "sources[{index}]: there's already a source named {:?}",
+                    source.name
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            bail!("invalid config:\n  {}", problems.join("\n  "));
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+
+    /// first sink of this type
+    #[must_use]
+    pub fn sink(&self, sink_type: SinkType) -> Option<&SinkConfig> {
+        self.sinks.iter().find(|sink| sink.sink_type == sink_type)
+    }
+}
+
+/// Command line flags that win over the config file, applied again on every
+/// reload
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub vrchat_dir: Option<PathBuf>,
+    /// only these sink types, the configured settings are kept for the ones
+    /// in the file
+    pub sinks: Option<Vec<SinkType>>,
+}
+
+impl ConfigOverrides {
+    fn apply(&self, config: &mut Config) -> Result<()> {
+        if let Some(vrchat_dir) = &self.vrchat_dir {
+            config.vrchat_dir = Some(vrchat_dir.clone());
+        }
+
+        if let Some(sink_types) = &self.sinks {
+            config
+                .sinks
+                .retain(|sink| sink_types.contains(&sink.sink_type));
+            for &sink_type in sink_types {
+                if config.sink(sink_type).is_none() {
+                    ensure!(
+                        sink_type.is_singleton(),
+                        "a {sink_type} output needs a [[sinks]] entry in the config"
+                    );
+                    config.sinks.push(SinkConfig::new(sink_type));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+static OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
+/// before the config is first loaded
+pub fn set_overrides(overrides: ConfigOverrides) {
+    OVERRIDES.set(overrides).unwrap();
+}
+
+static CONFIG: LazyLock<RwLock<Arc<Config>>> = LazyLock::new(RwLock::default);
+
+#[must_use]
+pub fn current_config() -> Arc<Config> {
+    CONFIG.read().unwrap().clone()
+}
+
+pub fn set_config(config: Config) {
+    *CONFIG.write().unwrap() = Arc::new(config);
+}
+
+/// `config check`, also makes sure the log directory can be found with it
+pub async fn check_config(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        println!("{} doesn't exist, using defaults", path.display());
+    }
+
+    let config = Config::load(path).await?;
+    let vrchat_dir_configured = config.vrchat_dir.is_some();
+    set_config(config);
+
+    match get_vrchat_dir().await {
+        Ok(vrchat_dir) => println!("log directory {}", vrchat_dir.display()),
+        Err(e) if vrchat_dir_configured => return Err(e.context("vrchat_dir")),
+        Err(e) => println!("warning: {e}"),
+    }
+
+    println!("OK");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigOverrides};
+    use crate::notifier::sinks::{SinkConfig, SinkType};
+
+    #[test]
+    fn default_config_round_trips() {
+        let text = toml::to_string_pretty(&Config::default()).unwrap();
+        assert_eq!(Config::parse(&text).unwrap(), Config::default());
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn lists_every_problem() {
+        let e = Config::parse(
+            r#"
+            debounce_ms = 100000
+            [audio]
+            volume = 2.0
+            [[sinks]]
+            type = "webhook"
+            [[sinks]]
+            type = "stdout"
+            [[sinks]]
+            type = "stdout"
+            [[sources]]
+            name = "alt"
+            path = "/mnt/alt/VRChat"
+            [[sources]]
+            name = "alt"
+            path = "/mnt/alt/output_log.txt"
+            [[sources]]
+            name = "my alt"
+            path = "/mnt/alt2/VRChat"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(e.contains("debounce_ms"), "{e}");
+        assert!(e.contains("audio.volume"), "{e}");
+        assert!(e.contains("sinks[0] (webhook): url is required"), "{e}");
+        assert!(
+            e.contains("sinks[2]: there can only be one stdout sink"),
+            "{e}"
+        );
+        assert!(
+            e.contains("sources[1]: there's already a source named \"alt\""),
+            "{e}"
+        );
+        assert!(e.contains("sources[2]: name \"my alt\""), "{e}");
+
+        assert!(Config::parse("debounce = 1").is_err());
+    }
+
+    #[test]
+    fn overrides_sinks() {
+        let mut config = Config::parse(
+            r#"
+            [[sinks]]
+            type = "stdout"
+            kinds = ["join"]
+            [[sinks]]
+            type = "file"
+            path = "notifications.log"
+            "#,
+        )
+        .unwrap();
+        let stdout = config.sinks[0].clone();
+
+        let overrides = ConfigOverrides {
+            vrchat_dir: None,
+            sinks: Some(vec![SinkType::Stdout, SinkType::Desktop]),
+        };
+        overrides.apply(&mut config).unwrap();
+        assert_eq!(
+            config.sinks,
+            vec![stdout, SinkConfig::new(SinkType::Desktop)]
+        );
+
+        let overrides = ConfigOverrides {
+            vrchat_dir: None,
+            sinks: Some(vec![SinkType::Webhook]),
+        };
+        assert!(overrides.apply(&mut config).is_err());
+    }
+}
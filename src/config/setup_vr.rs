@@ -1,23 +1,31 @@
-use std::{env::current_exe, path::PathBuf};
+use std::{
+    env::current_exe,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use tokio::{
     fs::{self, File},
     io::AsyncWriteExt,
 };
 
 use super::utils::get_data_dir;
-use crate::vr::manifest::VrManifest;
+use crate::vr::manifest::{Platform, VrManifest};
+
+const MANIFEST_FILE_NAME: &str = "manifest.vrmanifest";
+
+pub async fn get_manifest_path() -> Result<PathBuf> {
+    Ok(get_data_dir().await?.join(MANIFEST_FILE_NAME))
+}
 
 /// returns `manifest_path`
 pub async fn setup_vr_files() -> Result<PathBuf> {
-    let data_dir = get_data_dir().await?;
-    let manifest_path = data_dir.join("manifest.vrmanifest");
+    let manifest_path = get_manifest_path().await?;
 
     let exe_path = copy_exe_to_data_dir().await?;
     let exe_name = exe_path.file_name().context("file_name None")?;
 
-    let manifest = VrManifest::new(&exe_name.to_string_lossy());
+    let manifest = VrManifest::new(Platform::current(), &exe_name.to_string_lossy());
     let bytes = serde_json::to_vec_pretty(&manifest)?;
 
     {
@@ -28,12 +36,78 @@ pub async fn setup_vr_files() -> Result<PathBuf> {
     Ok(manifest_path)
 }
 
-async fn copy_exe_to_data_dir() -> Result<PathBuf> {
+/// Deletes what `setup_vr_files` created, returning the paths that were
+/// there.
+///
+/// On windows the copied exe is left alone if it's the one running, it can't
+/// be deleted while it is.
+pub async fn remove_vr_files() -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+
+    for path in [get_manifest_path().await?, get_copied_exe_path().await?] {
+        if !path.is_file() {
+            continue;
+        }
+        if cfg!(target_os = "windows") && is_current_exe(&path) {
+            println!(
+                "not deleting {}, windows can't while it's running",
+                path.display()
+            );
+            continue;
+        }
+
+        fs::remove_file(&path)
+            .await
+            .with_context(|| format!("deleting {path:?}"))?;
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// Makes sure the copy `setup_vr_files` made is the same as the running exe,
+/// returning its path.
+pub async fn check_installed_exe() -> Result<PathBuf> {
+    let exe_path = get_copied_exe_path().await?;
+    ensure!(
+        exe_path.is_file(),
+        "{} doesn't exist, run install",
+        exe_path.display()
+    );
+
+    if !is_current_exe(&exe_path) {
+        let installed = fs::read(&exe_path)
+            .await
+            .with_context(|| format!("reading {exe_path:?}"))?;
+        ensure!(
+            installed == fs::read(current_exe()?).await?,
+            "{} is a different version, run install again",
+            exe_path.display()
+        );
+    }
+
+    Ok(exe_path)
+}
+
+/// where `copy_exe_to_data_dir` puts it
+async fn get_copied_exe_path() -> Result<PathBuf> {
     let data_dir = get_data_dir().await?;
 
     let current_exe_path = current_exe()?;
-    let exe_path = data_dir.join(current_exe_path.file_name().context("file_name None")?);
-    fs::copy(&current_exe_path, &exe_path).await?;
+    Ok(data_dir.join(current_exe_path.file_name().context("file_name None")?))
+}
+
+fn is_current_exe(path: &Path) -> bool {
+    let current_exe_path = current_exe().and_then(|path| path.canonicalize());
+    matches!(
+        (current_exe_path, path.canonicalize()),
+        (Ok(current_exe_path), Ok(path)) if current_exe_path == path
+    )
+}
+
+async fn copy_exe_to_data_dir() -> Result<PathBuf> {
+    let exe_path = get_copied_exe_path().await?;
+    fs::copy(current_exe()?, &exe_path).await?;
 
     Ok(exe_path)
 }
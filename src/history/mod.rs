@@ -0,0 +1,497 @@
+use std::{
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::{bail, Error, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use time::PrimitiveDateTime;
+
+use crate::{
+    config::utils::get_data_dir,
+    parser::{event::Player, world::WorldInstance},
+};
+
+const HISTORY_FILE_NAME: &str = "history.sqlite3";
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+pub enum HistoryKind {
+    Join,
+    Leave,
+}
+
+impl Display for HistoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Join => "join",
+            Self::Leave => "leave",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for HistoryKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "join" => Self::Join,
+            "leave" => Self::Leave,
+            _ => bail!("unknown history kind {s:?}"),
+        })
+    }
+}
+
+/// A join or leave we saw
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HistoryRecord {
+    pub kind: HistoryKind,
+    /// local time from the log
+    pub timestamp: PrimitiveDateTime,
+    pub player: Player,
+    pub world: Option<WorldInstance>,
+}
+
+impl HistoryRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let kind: String = row.get("kind")?;
+        let world_id: Option<String> = row.get("world_id")?;
+        let instance_id: Option<String> = row.get("instance_id")?;
+        let world_name: Option<String> = row.get("world_name")?;
+
+        let world = world_id
+            .zip(instance_id)
+            .and_then(|(world_id, instance_id)| {
+                format!("{world_id}:{instance_id}")
+                    .parse::<WorldInstance>()
+                    .ok()
+            })
+            .map(|world| WorldInstance {
+                world_name,
+                ..world
+            });
+
+        Ok(Self {
+            kind: kind.parse().map_err(|e: Error| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+            })?,
+            timestamp: row.get("timestamp")?,
+            player: Player {
+                user_id: row.get("user_id")?,
+                display_name: row.get("display_name")?,
+            },
+            world,
+        })
+    }
+}
+
+/// Someone showing up under a different display name
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NameChange {
+    /// `usr_...`
+    pub user_id: String,
+    pub old_name: String,
+    pub new_name: String,
+    /// when we first saw the new name
+    pub timestamp: PrimitiveDateTime,
+}
+
+/// How often we've seen someone before
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Encounters {
+    /// times they joined
+    pub joins: u64,
+    pub last: Option<HistoryRecord>,
+}
+
+/// Every join and leave, kept in sqlite
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                user_id TEXT,
+                display_name TEXT NOT NULL,
+                world_id TEXT,
+                instance_id TEXT,
+                world_name TEXT,
+                -- the same log can be read more than once
+                UNIQUE (kind, timestamp, display_name)
+            );
+            CREATE INDEX IF NOT EXISTS history_user_id ON history (user_id);
+            CREATE INDEX IF NOT EXISTS history_display_name ON history (display_name);
+            CREATE INDEX IF NOT EXISTS history_instance ON history (world_id, instance_id);
+            CREATE INDEX IF NOT EXISTS history_timestamp ON history (timestamp);
+
+            CREATE TABLE IF NOT EXISTS name_changes (
+                id INTEGER PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                old_name TEXT NOT NULL,
+                new_name TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                UNIQUE (user_id, timestamp)
+            );
+            ",
+        )?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn record(&self, record: &HistoryRecord) -> Result<()> {
+        let world = record.world.as_ref();
+        self.connection.execute(
+            "INSERT OR IGNORE INTO history
+                (kind, timestamp, user_id, display_name, world_id, instance_id, world_name)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.kind.to_string(),
+                record.timestamp,
+                record.player.user_id,
+                record.player.display_name,
+                world.map(|world| &world.world_id),
+                world.map(|world| &world.instance_id),
+                world.and_then(|world| world.world_name.as_ref()),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn record_name_change(&self, name_change: &NameChange) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR IGNORE INTO name_changes (user_id, old_name, new_name, timestamp)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![
+                name_change.user_id,
+                name_change.old_name,
+                name_change.new_name,
+                name_change.timestamp,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// the display name `user_id` last had before `before`
+    pub fn last_name(&self, user_id: &str, before: PrimitiveDateTime) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT display_name FROM history
+                WHERE user_id = ?1 AND timestamp < ?2
+                ORDER BY timestamp DESC, id DESC
+                LIMIT 1",
+                params![user_id, before],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// oldest first
+    pub fn name_changes(&self, user_id: &str) -> Result<Vec<NameChange>> {
+        let mut statement = self.connection.prepare(
+            "SELECT user_id, old_name, new_name, timestamp FROM name_changes
+            WHERE user_id = ?1
+            ORDER BY timestamp, id",
+        )?;
+        let name_changes = statement
+            .query_map(params![user_id], |row| {
+                Ok(NameChange {
+                    user_id: row.get(0)?,
+                    old_name: row.get(1)?,
+                    new_name: row.get(2)?,
+                    timestamp: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(name_changes)
+    }
+
+    /// `usr_...` or a display name, matched ignoring case
+    pub fn last_seen(&self, user_id_or_name: &str) -> Result<Option<HistoryRecord>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT * FROM history
+                WHERE user_id = ?1 OR display_name = ?1 COLLATE NOCASE
+                ORDER BY timestamp DESC
+                LIMIT 1",
+                params![user_id_or_name],
+                HistoryRecord::from_row,
+            )
+            .optional()?)
+    }
+
+    /// Earlier joins and leaves of `player`, by user id, or display name for
+    /// logs without ids.
+    pub fn encounters_before(
+        &self,
+        player: &Player,
+        before: PrimitiveDateTime,
+    ) -> Result<Encounters> {
+        const PLAYER_MATCHES: &str = "((?1 IS NOT NULL AND user_id = ?1)
+            OR (?1 IS NULL AND display_name = ?2))
+            AND timestamp < ?3";
+        let params = params![player.user_id, player.display_name, before];
+
+        let joins = self.connection.query_row(
+            &format!("SELECT COUNT(*) FROM history WHERE kind = 'join' AND {PLAYER_MATCHES}"),
+            params,
+            |row| row.get(0),
+        )?;
+        let last = self
+            .connection
+            .query_row(
+                &format!(
+                    "SELECT * FROM history WHERE {PLAYER_MATCHES}
+                    ORDER BY timestamp DESC, id DESC
+                    LIMIT 1"
+                ),
+                params,
+                HistoryRecord::from_row,
+            )
+            .optional()?;
+
+        Ok(Encounters { joins, last })
+    }
+
+    /// everything between `from` and `to`, oldest first
+    pub fn between(
+        &self,
+        from: PrimitiveDateTime,
+        to: PrimitiveDateTime,
+    ) -> Result<Vec<HistoryRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT * FROM history
+            WHERE timestamp >= ?1 AND timestamp <= ?2
+            ORDER BY timestamp, id",
+        )?;
+        let records = statement
+            .query_map(params![from, to], HistoryRecord::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(records)
+    }
+
+    /// Records and returns the old name if `player` had a different one the
+    /// last time we saw them.
+    pub fn check_name_change(
+        &self,
+        player: &Player,
+        timestamp: PrimitiveDateTime,
+    ) -> Result<Option<String>> {
+        let Some(user_id) = &player.user_id else {
+            return Ok(None);
+        };
+        let Some(old_name) = self.last_name(user_id, timestamp)? else {
+            return Ok(None);
+        };
+        if old_name == player.display_name {
+            return Ok(None);
+        }
+
+        self.record_name_change(&NameChange {
+            user_id: user_id.clone(),
+            old_name: old_name.clone(),
+            new_name: player.display_name.clone(),
+            timestamp,
+        })?;
+        Ok(Some(old_name))
+    }
+
+    /// joins and leaves in one instance, oldest first
+    pub fn in_instance(&self, world: &WorldInstance) -> Result<Vec<HistoryRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT * FROM history
+            WHERE world_id = ?1 AND instance_id = ?2
+            ORDER BY timestamp, id",
+        )?;
+        let records = statement
+            .query_map(
+                params![world.world_id, world.instance_id],
+                HistoryRecord::from_row,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(records)
+    }
+}
+
+static HISTORY: LazyLock<Mutex<Option<History>>> = LazyLock::new(Mutex::default);
+
+/// Opens the history in the data directory, nothing is recorded until this
+/// is called.
+pub async fn start_history() -> Result<()> {
+    let path = get_data_dir().await?.join(HISTORY_FILE_NAME);
+    let history = tokio::task::spawn_blocking(move || History::open(&path)).await??;
+    *HISTORY.lock().unwrap() = Some(history);
+    Ok(())
+}
+
+/// `None` if the history isn't open
+pub fn with_history<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&History) -> R,
+{
+    HISTORY.lock().unwrap().as_ref().map(f)
+}
+
+pub fn record(record: &HistoryRecord) {
+    if let Some(Err(e)) = with_history(|history| history.record(record)) {
+        eprintln!("recording history: {e:?}");
+    }
+}
+
+/// Records and returns the old name if `player` had a different one the last
+/// time we saw them.
+#[must_use]
+pub fn check_name_change(player: &Player, timestamp: PrimitiveDateTime) -> Option<String> {
+    let result = with_history(|history| history.check_name_change(player, timestamp))?;
+
+    result.unwrap_or_else(|e| {
+        eprintln!("checking name change: {e:?}");
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{History, HistoryKind, HistoryRecord, NameChange};
+    use crate::{
+        notifier::MessageEvent,
+        parser::{event::Player, world::WorldInstance},
+    };
+
+    #[test]
+    fn records_and_queries() {
+        let history = History::open_in_memory().unwrap();
+        let mut world = "wrld_1:12345~region(eu)".parse::<WorldInstance>().unwrap();
+        world.world_name = Some("Test World".to_string());
+
+        let join = HistoryRecord {
+            kind: HistoryKind::Join,
+            timestamp: datetime!(2024-01-01 20:00:00),
+            player: Player::parse("SpiralP (usr_1)"),
+            world: Some(world.clone()),
+        };
+        let leave = HistoryRecord {
+            kind: HistoryKind::Leave,
+            timestamp: datetime!(2024-01-01 21:00:00),
+            ..join.clone()
+        };
+        history.record(&join).unwrap();
+        history.record(&join).unwrap();
+        history.record(&leave).unwrap();
+
+        assert_eq!(history.last_seen("usr_1").unwrap(), Some(leave.clone()));
+        assert_eq!(history.last_seen("spiralp").unwrap(), Some(leave.clone()));
+        assert_eq!(history.last_seen("usr_2").unwrap(), None);
+
+        assert_eq!(
+            history
+                .between(
+                    datetime!(2024-01-01 19:00:00),
+                    datetime!(2024-01-01 20:30:00)
+                )
+                .unwrap(),
+            vec![join.clone()]
+        );
+        assert_eq!(
+            history.in_instance(&world).unwrap(),
+            vec![join.clone(), leave.clone()]
+        );
+
+        let encounters = history
+            .encounters_before(&join.player, datetime!(2024-01-02 20:00:00))
+            .unwrap();
+        assert_eq!(encounters.joins, 1);
+        assert_eq!(encounters.last, Some(leave));
+        let encounters = history
+            .encounters_before(&join.player, join.timestamp)
+            .unwrap();
+        assert_eq!(encounters.joins, 0);
+        assert_eq!(encounters.last, None);
+
+        let later = datetime!(2024-01-02 20:00:00);
+        assert_eq!(
+            history.last_name("usr_1", later).unwrap().as_deref(),
+            Some("SpiralP")
+        );
+        assert_eq!(history.last_name("usr_1", join.timestamp).unwrap(), None);
+
+        let name_change = NameChange {
+            user_id: "usr_1".to_string(),
+            old_name: "SpiralP".to_string(),
+            new_name: "SpiralQ".to_string(),
+            timestamp: later,
+        };
+        history.record_name_change(&name_change).unwrap();
+        history.record_name_change(&name_change).unwrap();
+        assert_eq!(history.name_changes("usr_1").unwrap(), vec![name_change]);
+    }
+
+    #[test]
+    fn detects_name_changes() {
+        let history = History::open_in_memory().unwrap();
+        history
+            .record(&HistoryRecord {
+                kind: HistoryKind::Join,
+                timestamp: datetime!(2024-01-01 20:00:00),
+                player: Player::parse("OldName (usr_1)"),
+                world: None,
+            })
+            .unwrap();
+
+        let later = datetime!(2024-01-02 20:00:00);
+        let player = Player::parse("NewName (usr_1)");
+        let former_name = history.check_name_change(&player, later).unwrap();
+        assert_eq!(former_name.as_deref(), Some("OldName"));
+        assert_eq!(
+            MessageEvent::Join {
+                player,
+                former_name,
+            }
+            .to_string(),
+            "NewName (formerly OldName) joined"
+        );
+        assert_eq!(
+            history.name_changes("usr_1").unwrap(),
+            vec![NameChange {
+                user_id: "usr_1".to_string(),
+                old_name: "OldName".to_string(),
+                new_name: "NewName".to_string(),
+                timestamp: later,
+            }]
+        );
+
+        // same name, someone new, or no user id to go by
+        for player in ["OldName (usr_1)", "Someone (usr_2)", "NewName"] {
+            assert_eq!(
+                history
+                    .check_name_change(&Player::parse(player), later)
+                    .unwrap(),
+                None
+            );
+        }
+    }
+}
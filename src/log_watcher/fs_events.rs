@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+/// Wakes up when something at a path changes, so we don't have to wait for
+/// the next poll.
+pub struct FsEvents {
+    /// `None` if the platform watcher couldn't be set up, callers keep polling
+    _watcher: Option<RecommendedWatcher>,
+    receiver: mpsc::Receiver<()>,
+}
+
+impl FsEvents {
+    /// any change to the file at `path`
+    #[must_use]
+    pub fn modified(path: &Path) -> Self {
+        // our own reads show up as access events
+        Self::new(path, |kind| !matches!(kind, EventKind::Access(_)))
+    }
+
+    /// files created in the directory at `path`
+    #[must_use]
+    pub fn created(path: &Path) -> Self {
+        Self::new(path, EventKind::is_create)
+    }
+
+    fn new(path: &Path, filter: fn(&EventKind) -> bool) -> Self {
+        // changes are coalesced, we only care that something happened
+        let (sender, receiver) = mpsc::channel(1);
+
+        let watcher = (|| {
+            let mut watcher =
+                notify::recommended_watcher(move |result: notify::Result<_>| match result {
+                    Ok(notify::Event { kind, .. }) => {
+                        if filter(&kind) {
+                            let _ = sender.try_send(());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("FsEvents: {e:?}");
+                    }
+                })?;
+            watcher.watch(path, RecursiveMode::NonRecursive)?;
+            notify::Result::Ok(watcher)
+        })();
+
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("FsEvents: falling back to polling {}: {e}", path.display());
+                None
+            }
+        };
+
+        Self {
+            _watcher: watcher,
+            receiver,
+        }
+    }
+
+    /// never resolves if the watcher couldn't be set up
+    pub async fn changed(&mut self) {
+        if self.receiver.recv().await.is_none() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -1,86 +1,383 @@
 #![allow(clippy::assigning_clones)]
 
-use std::time::Duration;
+pub mod fs_events;
 
-use anyhow::Result;
-use tokio::{sync::broadcast, task::JoinHandle, time::MissedTickBehavior};
+use std::path::PathBuf;
 
-use crate::parser::{utils::get_newest_log_path, LogParser};
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, sync::broadcast, task::JoinHandle, time::MissedTickBehavior};
 
-pub struct LogWatcher {}
+use self::fs_events::FsEvents;
+use crate::{
+    config::{resume::ResumeState, settings::current_config},
+    parser::{
+        utils::{get_newest_log_path_in, get_vrchat_dir},
+        LogParser, StartPosition,
+    },
+};
+
+/// A `[[sources]]` entry in the config file, another log to watch besides the
+/// main one, like a second game client's
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    /// shown on its notifications
+    pub name: String,
+    /// a log directory to follow the newest log in, or a single log file
+    pub path: PathBuf,
+}
+
+impl SourceConfig {
+    pub fn validate(&self) -> Result<()> {
+        // also used in the resume file name
+        ensure!(
+            !self.name.is_empty()
+                && self
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "name {:?} should be letters, numbers, - or _",
+            self.name
+        );
+
+        Ok(())
+    }
+}
+
+pub struct LogWatcher {
+    /// a log directory, or a single log file
+    path: PathBuf,
+    /// name of the log source, `None` for the main one
+    source: Option<String>,
+    newest_log_path: Option<PathBuf>,
+    first_check: bool,
+    /// where a previous run stopped reading
+    resume_state: Option<ResumeState>,
+}
 
 impl LogWatcher {
     #[must_use]
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(path: PathBuf, source: Option<String>, resume_state: Option<ResumeState>) -> Self {
+        Self {
+            path,
+            source,
+            newest_log_path: None,
+            first_check: true,
+            resume_state,
+        }
     }
 
-    pub async fn run_loop(self) -> Result<()> {
-        let mut newest_log_path = None;
+    /// Returns the newest log file if it changed since the last check.
+    ///
+    /// A log that already existed when we started has old sessions in it, so
+    /// only new lines are read, unless a previous run was reading it, then
+    /// we continue from where it stopped. Logs created afterwards are read
+    /// from the start so we don't miss anything written before we noticed
+    /// the file.
+    pub async fn check_newest_log(&mut self) -> Result<Option<(PathBuf, StartPosition)>> {
+        let current_newest_log_path = if fs::metadata(&self.path)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            Some(self.path.clone())
+        } else {
+            get_newest_log_path_in(&self.path).await?
+        };
+
+        let start_position = if self.first_check {
+            match self.resume_state.take() {
+                Some(resume_state)
+                    if Some(&resume_state.log_path) == current_newest_log_path.as_ref()
+                        && fs::metadata(&resume_state.log_path)
+                            .await
+                            .is_ok_and(|metadata| metadata.len() >= resume_state.offset) =>
+                {
+                    StartPosition::Resume(resume_state)
+                }
+                _ => StartPosition::End,
+            }
+        } else {
+            StartPosition::Start
+        };
+        self.first_check = false;
+
+        if self.newest_log_path == current_newest_log_path {
+            return Ok(None);
+        }
+        self.newest_log_path = current_newest_log_path.clone();
+
+        Ok(current_newest_log_path.map(|log_path| (log_path, start_position)))
+    }
+
+    pub async fn run_loop(mut self) -> Result<()> {
         let mut watch_handle: Option<JoinHandle<()>> = None;
 
-        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        // new log files show up right away through fs events, the interval is
+        // a fallback for when those are missed or unsupported
+        let mut fs_events = FsEvents::created(&self.path);
+
+        let mut interval = tokio::time::interval(current_config().poll.log_dir_interval());
         interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
+        // so an unreachable source is only reported once, not every tick
+        let mut failing = false;
+
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {},
+                () = fs_events.changed() => {},
+            }
 
-            let current_newest_log_path = get_newest_log_path().await.unwrap();
+            if failing {
+                // continue where its parser stopped if the same log comes back
+                self.resume_state = load_resume_state(self.source.as_deref()).await;
+            }
+
+            let newest_log = match self.check_newest_log().await {
+                Ok(newest_log) => {
+                    failing = false;
+                    newest_log
+                }
+                // another source, like a drive that isn't mounted yet, might
+                // come back
+                Err(e) if self.source.is_some() => {
+                    if !failing {
+                        failing = true;
+                        eprintln!("log source {:?}: {e:#}, retrying", self.source);
+                        self.newest_log_path = None;
+                        self.first_check = true;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
 
-            if newest_log_path != current_newest_log_path {
-                println!("new {current_newest_log_path:?}");
-                newest_log_path = current_newest_log_path.clone();
+            if let Some((log_path, start_position)) = newest_log {
+                println!("new {log_path:?}");
 
                 if let Some(old_handle) = watch_handle.take() {
                     old_handle.abort();
                 }
-                if let Some(log_path) = current_newest_log_path {
-                    watch_handle = Some(tokio::spawn(async move {
-                        let f = {
-                            let log_path = log_path.clone();
-
-                            async move {
-                                let parser = LogParser::new(&log_path)?;
-                                parser.read_loop().await?;
-                                anyhow::Ok(())
-                            }
-                        };
-
-                        if let Err(e) = f.await {
-                            eprintln!("watch_handle: {log_path:?} {e:?}");
+                let source = self.source.clone();
+                watch_handle = Some(tokio::spawn(async move {
+                    let f = {
+                        let log_path = log_path.clone();
+
+                        async move {
+                            let parser = LogParser::new(&log_path, source)?;
+                            parser.read_loop(start_position).await?;
+                            anyhow::Ok(())
                         }
-                    }));
-                }
+                    };
+
+                    if let Err(e) = f.await {
+                        eprintln!("watch_handle: {log_path:?} {e:?}");
+                    }
+                }));
             }
         }
     }
 }
 
-impl Default for LogWatcher {
-    fn default() -> Self {
-        Self::new()
+/// Watches the main log directory and every configured source, each with its
+/// own parser. Shuts down if the main one stops, other sources keep retrying
+/// while they can't be read.
+pub async fn start_log_watcher(shutdown_send: broadcast::Sender<()>) -> Result<()> {
+    let mut watchers = vec![LogWatcher::new(
+        get_vrchat_dir().await?,
+        None,
+        load_resume_state(None).await,
+    )];
+    for source in &current_config().sources {
+        watchers.push(LogWatcher::new(
+            source.path.clone(),
+            Some(source.name.clone()),
+            load_resume_state(Some(&source.name)).await,
+        ));
     }
-}
 
-pub async fn start_log_watcher(shutdown_send: broadcast::Sender<()>) -> Result<()> {
-    let mut shutdown_recv = shutdown_send.subscribe();
-    let watcher = LogWatcher::new();
-
-    tokio::spawn(async move {
-        tokio::select! {
-            _ = shutdown_recv.recv() => {
-                println!("start_log_watcher got shutdown");
-            },
-            result = watcher.run_loop() => {
-                if let Err(e) = result {
-                    eprintln!("start_log_watcher: {e:?}");
+    for watcher in watchers {
+        let shutdown_send = shutdown_send.clone();
+        let mut shutdown_recv = shutdown_send.subscribe();
+        let source = watcher.source.clone();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = shutdown_recv.recv() => {
+                    println!("start_log_watcher got shutdown");
+                },
+                result = watcher.run_loop() => {
+                    if let Err(e) = result {
+                        eprintln!("start_log_watcher: {source:?} {e:?}");
+                    }
+                    // only the main one gives up, the others keep retrying
+                    if source.is_none() {
+                        let _ = shutdown_send.send(());
+                    }
                 }
             }
-        }
 
-        println!("start_log_watcher end");
-        let _ = shutdown_send.send(());
-    });
+            println!("start_log_watcher end");
+        });
+    }
 
     Ok(())
 }
+
+async fn load_resume_state(source: Option<&str>) -> Option<ResumeState> {
+    ResumeState::load(source).await.unwrap_or_else(|e| {
+        eprintln!("loading resume state: {e:?}");
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        io::Write,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    };
+
+    use super::LogWatcher;
+    use crate::{config::resume::ResumeState, parser::StartPosition, roster::Roster};
+
+    fn write_log(dir: &Path, file_name: &str, age: Duration) -> PathBuf {
+        let path = dir.join(file_name);
+        let mut f = File::create(&path).unwrap();
+        writeln!(
+            f,
+            "2024.01.01 20:00:00 Log        -  [Behaviour] OnPlayerJoined SpiralP (usr_1)"
+        )
+        .unwrap();
+        f.set_modified(SystemTime::now() - age).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn tails_startup_log_and_reads_new_logs_from_start() {
+        let dir = tempfile::tempdir().unwrap();
+        write_log(
+            dir.path(),
+            "output_log_2024-01-01_19-00-00.txt",
+            Duration::from_secs(100),
+        );
+        let startup_log = write_log(
+            dir.path(),
+            "output_log_2024-01-01_20-00-00.txt",
+            Duration::from_secs(10),
+        );
+        write_log(dir.path(), "Player.log", Duration::ZERO);
+
+        let mut watcher = LogWatcher::new(dir.path().to_path_buf(), None, None);
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((startup_log, StartPosition::End))
+        );
+        assert_eq!(watcher.check_newest_log().await.unwrap(), None);
+
+        let new_log = write_log(
+            dir.path(),
+            "output_log_2024-01-01_21-00-00.txt",
+            Duration::ZERO,
+        );
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((new_log, StartPosition::Start))
+        );
+        assert_eq!(watcher.check_newest_log().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn reads_first_log_from_start_if_created_after_startup() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut watcher = LogWatcher::new(dir.path().to_path_buf(), None, None);
+        assert_eq!(watcher.check_newest_log().await.unwrap(), None);
+
+        let new_log = write_log(
+            dir.path(),
+            "output_log_2024-01-01_21-00-00.txt",
+            Duration::ZERO,
+        );
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((new_log, StartPosition::Start))
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_startup_log_from_saved_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let startup_log = write_log(
+            dir.path(),
+            "output_log_2024-01-01_20-00-00.txt",
+            Duration::ZERO,
+        );
+
+        let resume_state = ResumeState::new(startup_log.clone(), 10, &Roster::new());
+        let mut watcher =
+            LogWatcher::new(dir.path().to_path_buf(), None, Some(resume_state.clone()));
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((startup_log.clone(), StartPosition::Resume(resume_state)))
+        );
+
+        // log was truncated or replaced since
+        let resume_state = ResumeState::new(startup_log.clone(), 1000, &Roster::new());
+        let mut watcher = LogWatcher::new(dir.path().to_path_buf(), None, Some(resume_state));
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((startup_log, StartPosition::End))
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_a_single_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = write_log(
+            dir.path(),
+            "output_log_2024-01-01_20-00-00.txt",
+            Duration::from_secs(10),
+        );
+
+        let mut watcher = LogWatcher::new(log.clone(), Some("alt".to_string()), None);
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((log, StartPosition::End))
+        );
+
+        write_log(
+            dir.path(),
+            "output_log_2024-01-01_21-00-00.txt",
+            Duration::ZERO,
+        );
+        assert_eq!(watcher.check_newest_log().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn picks_up_a_source_once_it_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("mnt");
+
+        let mut watcher = LogWatcher::new(log_dir.clone(), Some("alt".to_string()), None);
+        assert!(watcher.check_newest_log().await.is_err());
+
+        std::fs::create_dir(&log_dir).unwrap();
+        let log = write_log(
+            &log_dir,
+            "output_log_2024-01-01_20-00-00.txt",
+            Duration::ZERO,
+        );
+        assert_eq!(
+            watcher.check_newest_log().await.unwrap(),
+            Some((log.clone(), StartPosition::End))
+        );
+
+        std::fs::remove_file(&log).unwrap();
+        let mut watcher = LogWatcher::new(log, Some("alt".to_string()), None);
+        assert!(watcher.check_newest_log().await.is_err());
+    }
+}
@@ -3,41 +3,114 @@
 #![allow(clippy::missing_errors_doc)]
 
 pub mod audio;
+pub mod cli;
 pub mod config;
+pub mod history;
 pub mod log_watcher;
 pub mod notifier;
 pub mod parser;
+pub mod replay;
+pub mod roster;
+pub mod session;
 pub mod vr;
 
-use std::env::args;
+use std::path::PathBuf;
 
 use anyhow::Result;
+use clap::Parser;
 use tokio::{signal, sync::broadcast};
 
 use crate::{
-    audio::start_audio,
+    audio::{start_audio, test_sound},
+    cli::{
+        config::config_command, history::history_command, ignore::ignore_command, set_verbosity,
+        status::status, Cli, Command, RunArgs,
+    },
+    config::{
+        reload::start_config_watcher,
+        settings::{current_config, set_config, set_overrides, Config, ConfigOverrides},
+    },
+    history::start_history,
     log_watcher::start_log_watcher,
-    vr::{runtime::start_runtime, setup::setup_vr},
+    notifier::sinks::register_configured_sinks,
+    replay::replay,
+    vr::{
+        runtime::{start_runtime, test_notify},
+        setup::{setup_vr, uninstall_vr},
+    },
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let arg = args().nth(1).unwrap_or_default();
+    let cli = Cli::parse();
+
+    set_verbosity(cli.verbose);
+    set_overrides(ConfigOverrides {
+        vrchat_dir: cli.log_dir,
+        sinks: cli.outputs,
+    });
+    let config_path = match cli.config {
+        Some(config_path) => config_path,
+        None => Config::path().await?,
+    };
+
+    match cli.command.unwrap_or(Command::Run(RunArgs::default())) {
+        Command::Run(args) => run(config_path, args.desktop || cli.run.desktop).await?,
+        Command::Install => {
+            setup_vr().await?;
+            println!("OK");
+        }
+        Command::Uninstall => {
+            uninstall_vr().await?;
+            println!("OK");
+        }
+        Command::Replay { log_path, speed } => {
+            set_config(Config::load(&config_path).await?);
+            replay(&log_path, speed).await?;
+        }
+        Command::Status => status(&config_path).await?,
+        Command::History(command) => history_command(command).await?,
+        Command::Config(command) => config_command(command, &config_path).await?,
+        Command::Ignore(command) => ignore_command(command).await?,
+        Command::TestNotify { text } => {
+            set_config(Config::load(&config_path).await?);
+            test_notify(&text).await?;
+        }
+        Command::TestSound { sound } => {
+            set_config(Config::load(&config_path).await?);
+            test_sound(&sound).await?;
+        }
+    }
 
-    if arg == "install" {
-        setup_vr().await?;
+    Ok(())
+}
 
-        println!("OK");
-        return Ok(());
-    }
+/// `desktop` keeps running without `SteamVR`, same as the config option
+async fn run(config_path: PathBuf, desktop: bool) -> Result<()> {
+    set_config(Config::load(&config_path).await?);
+
+    let desktop = current_config().desktop || desktop;
+
+    register_configured_sinks(&current_config().sinks);
 
     let (shutdown_send, mut shutdown_recv) = broadcast::channel(8);
 
+    start_config_watcher(shutdown_send.clone(), config_path).await?;
+
+    if let Err(e) = start_history().await {
+        eprintln!("running without history: {e:?}");
+    }
+
     start_log_watcher(shutdown_send.clone()).await?;
 
-    start_runtime(shutdown_send.clone()).await?;
+    start_runtime(shutdown_send.clone(), desktop).await?;
 
-    start_audio(shutdown_send.clone()).await?;
+    if let Err(e) = start_audio(shutdown_send.clone()).await {
+        if !desktop {
+            return Err(e);
+        }
+        eprintln!("running without audio: {e}");
+    }
 
     tokio::select! {
         _ = signal::ctrl_c() => {
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, PrimitiveDateTime};
+
+use crate::{
+    config::settings::current_config,
+    history::{with_history, Encounters},
+    parser::event::Player,
+};
+
+/// What's added to join notifications from the history
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnrichOptions {
+    /// "last seen 3 days ago in World"
+    pub last_seen: bool,
+    /// "met 12 times before"
+    pub encounter_count: bool,
+}
+
+impl Default for EnrichOptions {
+    fn default() -> Self {
+        Self {
+            last_seen: true,
+            encounter_count: true,
+        }
+    }
+}
+
+/// A line about earlier encounters with someone who joined at `timestamp`,
+/// `None` if turned off or there's no history.
+#[must_use]
+pub fn describe_join(player: &Player, timestamp: PrimitiveDateTime) -> Option<String> {
+    let options = current_config().enrich.clone();
+    if !options.last_seen && !options.encounter_count {
+        return None;
+    }
+
+    let encounters = match with_history(|history| history.encounters_before(player, timestamp))? {
+        Ok(encounters) => encounters,
+        Err(e) => {
+            eprintln!("describe_join: {e:?}");
+            return None;
+        }
+    };
+
+    describe(&options, &encounters, timestamp)
+}
+
+fn describe(
+    options: &EnrichOptions,
+    encounters: &Encounters,
+    timestamp: PrimitiveDateTime,
+) -> Option<String> {
+    let Some(last) = &encounters.last else {
+        return Some("first time meeting".to_string());
+    };
+
+    let mut parts = Vec::new();
+    if options.last_seen {
+        let mut part = format!("last seen {}", ago(timestamp - last.timestamp));
+        if let Some(world) = &last.world {
+            let _ = write!(part, " in {world}");
+        }
+        parts.push(part);
+    }
+    if options.encounter_count && encounters.joins > 0 {
+        parts.push(format!("met {} before", plural(encounters.joins, "time")));
+    }
+
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+fn plural(count: u64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+/// `3 days ago`, `just now`
+#[must_use]
+pub fn ago(duration: Duration) -> String {
+    let minutes = duration.whole_minutes().unsigned_abs();
+    let hours = duration.whole_hours().unsigned_abs();
+    if minutes < 1 {
+        "just now".to_string()
+    } else if minutes < 60 {
+        format!("{} ago", plural(minutes, "minute"))
+    } else if hours < 48 {
+        format!("{} ago", plural(hours, "hour"))
+    } else {
+        format!(
+            "{} ago",
+            plural(duration.whole_days().unsigned_abs(), "day")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{describe, EnrichOptions};
+    use crate::{
+        history::{Encounters, HistoryKind, HistoryRecord},
+        parser::{event::Player, world::WorldInstance},
+    };
+
+    #[test]
+    fn describes_earlier_encounters() {
+        let options = EnrichOptions::default();
+        let now = datetime!(2024-01-04 20:00:00);
+
+        let first = Encounters {
+            joins: 0,
+            last: None,
+        };
+        assert_eq!(
+            describe(&options, &first, now).as_deref(),
+            Some("first time meeting")
+        );
+
+        let mut world = "wrld_1:12345".parse::<WorldInstance>().unwrap();
+        world.world_name = Some("Test World".to_string());
+        let seen = Encounters {
+            joins: 12,
+            last: Some(HistoryRecord {
+                kind: HistoryKind::Leave,
+                timestamp: datetime!(2024-01-01 19:00:00),
+                player: Player::parse("SpiralP (usr_1)"),
+                world: Some(world),
+            }),
+        };
+        assert_eq!(
+            describe(&options, &seen, now).as_deref(),
+            Some("last seen 3 days ago in Test World, met 12 times before")
+        );
+
+        let options = EnrichOptions {
+            last_seen: false,
+            ..options
+        };
+        assert_eq!(
+            describe(&options, &seen, now).as_deref(),
+            Some("met 12 times before")
+        );
+    }
+}
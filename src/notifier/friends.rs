@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::parser::event::Player;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Others {
+    /// grouped into "N players joined"
+    Grouped,
+    /// no toast or sound, only friends are announced
+    Silent,
+}
+
+/// Players that get announced on their own
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FriendsOptions {
+    /// `usr_...` ids, or display names which are matched ignoring case
+    pub players: Vec<String>,
+    /// `{name}` is replaced with the display name
+    pub join_title: String,
+    pub leave_title: String,
+    /// wav files, the normal sounds pitched up if unset
+    pub join_sound: Option<PathBuf>,
+    pub leave_sound: Option<PathBuf>,
+    /// what happens to everyone else
+    pub others: Others,
+}
+
+impl Default for FriendsOptions {
+    fn default() -> Self {
+        Self {
+            players: Vec::new(),
+            join_title: "Friend {name} joined".to_string(),
+            leave_title: "Friend {name} left".to_string(),
+            join_sound: None,
+            leave_sound: None,
+            others: Others::Grouped,
+        }
+    }
+}
+
+impl FriendsOptions {
+    #[must_use]
+    pub fn contains(&self, player: &Player) -> bool {
+        self.players.iter().any(|entry| {
+            if entry.starts_with("usr_") {
+                player.user_id.as_ref() == Some(entry)
+            } else {
+                entry.eq_ignore_ascii_case(&player.display_name)
+            }
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for title in [&self.join_title, &self.leave_title] {
+            ensure!(title.contains("{name}"), "{title:?} is missing {{name}}");
+        }
+
+        for sound in [&self.join_sound, &self.leave_sound].into_iter().flatten() {
+            ensure!(sound.is_file(), "sound {sound:?} doesn't exist");
+        }
+
+        Ok(())
+    }
+}
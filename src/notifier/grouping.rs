@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameDisplay {
+    /// "3 players joined" with a name per line below
+    Lines,
+    /// "A, B and C joined"
+    Inline,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Truncation {
+    /// drop the names, "7 players joined"
+    Count,
+    /// "A, B, C and 4 others"
+    Others,
+}
+
+/// How several joins or leaves at once are shown
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupingOptions {
+    pub display: NameDisplay,
+    /// more names than this get truncated
+    pub max_names: usize,
+    pub truncation: Truncation,
+}
+
+impl Default for GroupingOptions {
+    fn default() -> Self {
+        Self {
+            display: NameDisplay::Lines,
+            max_names: 6,
+            truncation: Truncation::Count,
+        }
+    }
+}
+
+/// Returns title and body lines for `names` that all did `verb`.
+#[must_use]
+pub fn group(
+    mut names: Vec<String>,
+    verb: &str,
+    options: &GroupingOptions,
+) -> (String, Vec<String>) {
+    if names.len() == 1 {
+        return (format!("{} {verb}", names.remove(0)), Vec::new());
+    }
+
+    let count = names.len();
+    let hidden = count.saturating_sub(options.max_names);
+    if hidden > 0 {
+        match options.truncation {
+            Truncation::Count => names.clear(),
+            Truncation::Others => names.truncate(options.max_names),
+        }
+    }
+
+    let count_title = format!("{count} players {verb}");
+    if names.is_empty() {
+        return (count_title, Vec::new());
+    }
+
+    match options.display {
+        NameDisplay::Lines => {
+            if hidden > 0 {
+                names.push(format!("and {}", others(hidden)));
+            }
+            (count_title, names)
+        }
+
+        NameDisplay::Inline => (format!("{} {verb}", join_names(names, hidden)), Vec::new()),
+    }
+}
+
+fn others(count: usize) -> String {
+    if count == 1 {
+        "1 other".to_string()
+    } else {
+        format!("{count} others")
+    }
+}
+
+/// "A, B and C", "A, B, C and 4 others"
+fn join_names(mut names: Vec<String>, hidden: usize) -> String {
+    if hidden > 0 {
+        return format!("{} and {}", names.join(", "), others(hidden));
+    }
+
+    let last = names.pop().unwrap_or_default();
+    if names.is_empty() {
+        last
+    } else {
+        format!("{} and {last}", names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group, GroupingOptions, NameDisplay, Truncation};
+
+    fn names(count: usize) -> Vec<String> {
+        ["A", "B", "C", "D", "E", "F", "G"][..count]
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn grouping(display: NameDisplay, truncation: Truncation) -> GroupingOptions {
+        GroupingOptions {
+            display,
+            max_names: 3,
+            truncation,
+        }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn groups_names() {
+        for display in [NameDisplay::Lines, NameDisplay::Inline] {
+            let options = grouping(display, Truncation::Count);
+            assert_eq!(
+                group(names(1), "joined", &options),
+                ("A joined".to_string(), Vec::new())
+            );
+        }
+
+        let options = grouping(NameDisplay::Lines, Truncation::Count);
+        // at the cutoff, all shown
+        assert_eq!(
+            group(names(3), "joined", &options),
+            ("3 players joined".to_string(), lines(&["A", "B", "C"]))
+        );
+        assert_eq!(
+            group(names(4), "joined", &options),
+            ("4 players joined".to_string(), Vec::new())
+        );
+
+        let options = grouping(NameDisplay::Lines, Truncation::Others);
+        assert_eq!(
+            group(names(4), "left", &options),
+            (
+                "4 players left".to_string(),
+                lines(&["A", "B", "C", "and 1 other"])
+            )
+        );
+        assert_eq!(
+            group(names(7), "left", &options),
+            (
+                "7 players left".to_string(),
+                lines(&["A", "B", "C", "and 4 others"])
+            )
+        );
+
+        let options = grouping(NameDisplay::Inline, Truncation::Count);
+        assert_eq!(
+            group(names(2), "joined", &options),
+            ("A and B joined".to_string(), Vec::new())
+        );
+        assert_eq!(
+            group(names(3), "joined", &options),
+            ("A, B and C joined".to_string(), Vec::new())
+        );
+        assert_eq!(
+            group(names(4), "joined", &options),
+            ("4 players joined".to_string(), Vec::new())
+        );
+
+        let options = grouping(NameDisplay::Inline, Truncation::Others);
+        assert_eq!(
+            group(names(7), "joined", &options),
+            ("A, B, C and 4 others joined".to_string(), Vec::new())
+        );
+    }
+}
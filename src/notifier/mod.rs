@@ -1,148 +1,425 @@
-use std::{cell::RefCell, collections::HashSet, fmt::Display, sync::LazyLock, time::Duration};
+pub mod enrich;
+pub mod friends;
+pub mod grouping;
+pub mod sinks;
+
+use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use anyhow::Result;
-use deunicode::deunicode;
-use tokio::sync::{mpsc, Mutex, MutexGuard, OnceCell};
+use serde::{Deserialize, Serialize};
+use time::{macros::format_description, OffsetDateTime, PrimitiveDateTime};
+use tokio::sync::{Mutex, OnceCell};
+
+use self::{enrich::describe_join, friends::Others, grouping::group};
+use crate::{
+    audio::{self, AudioEvent},
+    config::{ignore::is_ignored, settings::current_config},
+    parser::{event::Player, world::WorldInstance},
+};
 
-use crate::audio::{self, AudioEvent};
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationKind {
+    Join,
+    Leave,
+    /// we joined an instance
+    Instance,
+    /// recap of an instance we left
+    Summary,
+    /// about the app itself
+    Status,
+}
 
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Notification {
-    pub sender: mpsc::Sender<String>,
-    pub receiver: Option<mpsc::Receiver<String>>,
+    /// when the notified thing happened, in local time
+    pub timestamp: PrimitiveDateTime,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: Vec<String>,
+    pub world: Option<WorldInstance>,
+    /// name of the log source, `None` for the main one
+    pub source: Option<String>,
 }
 
 impl Notification {
     #[must_use]
-    pub fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(32);
+    pub fn new(kind: NotificationKind, title: &str) -> Self {
         Self {
-            sender,
-            receiver: Some(receiver),
+            timestamp: now_local(),
+            kind,
+            title: title.to_string(),
+            body: Vec::new(),
+            world: None,
+            source: None,
         }
     }
-}
 
-impl Default for Notification {
-    fn default() -> Self {
-        Self::new()
+    /// `[source] title` for notifications from other log sources
+    #[must_use]
+    pub fn tagged_title(&self) -> String {
+        match &self.source {
+            Some(source) => format!("[{source}] {}", self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn format_timestamp(&self) -> String {
+        self.timestamp
+            .format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second]"
+            ))
+            .unwrap_or_else(|_| "unknown time".to_string())
     }
 }
 
-pub static NOTIFICATION: LazyLock<std::sync::Mutex<RefCell<Notification>>> =
-    LazyLock::new(|| std::sync::Mutex::new(RefCell::new(Notification::new())));
+#[must_use]
+pub fn now_local() -> PrimitiveDateTime {
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    PrimitiveDateTime::new(now.date(), now.time())
+}
 
-pub async fn notify(text: &str) -> Result<()> {
-    let date = time::OffsetDateTime::now_local()
-        .ok()
-        .and_then(|dt| {
-            dt.format(&time::format_description::well_known::Rfc2822)
-                .ok()
-        })
-        .unwrap_or_else(|| "unknown time".to_string());
-    let text = deunicode(text);
-    println!("{date} | {text}");
-
-    let sender = {
-        let guard = NOTIFICATION.lock().unwrap();
-        let cell = guard.borrow_mut();
-        cell.sender.clone()
-    };
+pub fn notify(text: &str) {
+    send_notification(&Notification::new(NotificationKind::Status, text));
+}
+
+/// send to every registered sink that wants this kind of notification
+pub fn send_notification(notification: &Notification) {
+    sinks::dispatch(notification);
+}
 
-    sender.send(text).await?;
+/// Which log a join or leave was read from, and the instance it happened in
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EventOrigin {
+    /// name of the log source, `None` for the main one
+    pub source: Option<String>,
+    pub world: Option<WorldInstance>,
+}
 
-    Ok(())
+/// Events waiting to be grouped, one batch per log source
+#[derive(Default)]
+struct Debounced {
+    source: Option<String>,
+    /// bumped every time the events are taken, so a stale timer doesn't flush
+    /// a newer batch early
+    generation: u64,
+    /// timestamp of the first event
+    timestamp: Option<PrimitiveDateTime>,
+    /// world at the first event
+    world: Option<WorldInstance>,
+    events: Vec<MessageEvent>,
+}
+
+struct DebouncedBatch {
+    timestamp: PrimitiveDateTime,
+    origin: EventOrigin,
+    events: Vec<MessageEvent>,
+}
+
+impl Debounced {
+    /// Takes the batch if `timestamp` is too late to be part of it.
+    ///
+    /// Groups by log time rather than wall time so lines read back to back
+    /// (replays, catching up) aren't all merged together.
+    fn take_stale(
+        &mut self,
+        timestamp: PrimitiveDateTime,
+        debounce: Duration,
+    ) -> Option<DebouncedBatch> {
+        if self
+            .timestamp
+            .is_some_and(|first| timestamp - first >= debounce)
+        {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    fn take(&mut self) -> Option<DebouncedBatch> {
+        self.generation += 1;
+        let timestamp = self.timestamp.take()?;
+        Some(DebouncedBatch {
+            timestamp,
+            origin: EventOrigin {
+                source: self.source.clone(),
+                world: self.world.take(),
+            },
+            events: self.events.drain(..).collect(),
+        })
+    }
 }
 
-static DEBOUNCED: OnceCell<Mutex<HashSet<MessageEvent>>> = OnceCell::const_new();
+static DEBOUNCED: OnceCell<Mutex<HashMap<Option<String>, Debounced>>> = OnceCell::const_new();
 
-async fn with_debounced<F, R>(f: F) -> R
+/// the batch of the log source named `source`
+async fn with_debounced<F, R>(source: Option<&str>, f: F) -> R
 where
-    F: FnOnce(MutexGuard<HashSet<MessageEvent>>) -> R,
+    F: FnOnce(&mut Debounced) -> R,
 {
     let debounced = DEBOUNCED
         .get_or_init(move || async move { Mutex::default() })
         .await;
-    let debounced = debounced.lock().await;
+    let mut debounced = debounced.lock().await;
+    let source = source.map(ToString::to_string);
+    let debounced = debounced
+        .entry(source.clone())
+        .or_insert_with(|| Debounced {
+            source,
+            ..Debounced::default()
+        });
     f(debounced)
 }
 
-const DEBOUNCE_DURATION: Duration = Duration::from_secs(1);
-
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub enum MessageEvent {
-    Join(String),
-    Leave(String),
+    Join {
+        player: Player,
+        /// their previous display name, if it changed since we last saw them
+        former_name: Option<String>,
+    },
+    Leave(Player),
+}
+
+impl MessageEvent {
+    #[must_use]
+    pub fn player(&self) -> &Player {
+        match self {
+            Self::Join { player, .. } | Self::Leave(player) => player,
+        }
+    }
+
+    /// `NewName (formerly OldName)` after a name change
+    #[must_use]
+    pub fn name(&self) -> String {
+        match self {
+            Self::Join {
+                player,
+                former_name: Some(former_name),
+            } => format!("{player} (formerly {former_name})"),
+            _ => self.player().to_string(),
+        }
+    }
 }
 
 impl Display for MessageEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Join(name) => write!(f, "{name} joined"),
-            Self::Leave(name) => write!(f, "{name} left"),
+            Self::Join { .. } => write!(f, "{} joined", self.name()),
+            Self::Leave(_) => write!(f, "{} left", self.name()),
         }
     }
 }
 
-pub async fn debounced_notify(event: MessageEvent) -> Result<()> {
-    with_debounced(move |mut debounced| {
-        let was_empty = debounced.is_empty();
+pub async fn debounced_notify(
+    origin: EventOrigin,
+    timestamp: PrimitiveDateTime,
+    event: MessageEvent,
+) -> Result<()> {
+    if is_ignored(event.player()).await {
+        return Ok(());
+    }
+
+    let config = current_config();
+    let debounce = config.debounce();
+    let source = origin.source.clone();
 
-        debounced.insert(event);
+    if config.friends.contains(event.player()) {
+        // never grouped, so no reason to wait, but keep the order
+        let stale = with_debounced(source.as_deref(), |debounced| {
+            debounced.take_stale(timestamp, debounce)
+        })
+        .await;
+        if let Some(batch) = stale {
+            send_debounced(batch);
+        }
 
+        send_friend_notification(origin, timestamp, &event);
+        return Ok(());
+    }
+    if config.friends.others == Others::Silent {
+        return Ok(());
+    }
+
+    let (stale, new_generation) = with_debounced(source.as_deref(), move |debounced| {
+        let stale = debounced.take_stale(timestamp, debounce);
+
+        let was_empty = debounced.events.is_empty();
+
+        if !debounced.events.contains(&event) {
+            debounced.events.push(event);
+        }
         if was_empty {
-            tokio::spawn(async move {
-                tokio::time::sleep(DEBOUNCE_DURATION).await;
-
-                let notifies = with_debounced(move |mut debounced| {
-                    let mut notifies: Vec<(String, Option<Vec<String>>)> = Vec::new();
-
-                    let mut join_messages = Vec::new();
-                    let mut leave_messages = Vec::new();
-                    for event in debounced.drain() {
-                        match event {
-                            MessageEvent::Join(_) => join_messages.push(event.to_string()),
-                            MessageEvent::Leave(_) => leave_messages.push(event.to_string()),
-                        }
-                    }
-
-                    if !join_messages.is_empty() {
-                        let _ = audio::handle_event(&AudioEvent::Join);
-                        notifies.push(group(join_messages, "players joined"));
-                    }
-
-                    if !leave_messages.is_empty() {
-                        let _ = audio::handle_event(&AudioEvent::Leave);
-                        notifies.push(group(leave_messages, "players left"));
-                    }
-
-                    notifies
-                })
-                .await;
-
-                for (title, _body_lines) in notifies {
-                    if let Err(e) = notify(&title).await {
-                        eprintln!("{e:?}");
-                    }
-                }
-            });
+            debounced.timestamp = Some(timestamp);
+            debounced.world = origin.world;
         }
 
-        Ok(())
+        (stale, was_empty.then_some(debounced.generation))
     })
-    .await
+    .await;
+
+    if let Some(batch) = stale {
+        send_debounced(batch);
+    }
+
+    if let Some(generation) = new_generation {
+        tokio::spawn(async move {
+            tokio::time::sleep(debounce).await;
+
+            let taken = with_debounced(source.as_deref(), move |debounced| {
+                if debounced.generation == generation {
+                    debounced.take()
+                } else {
+                    None
+                }
+            })
+            .await;
+
+            if let Some(batch) = taken {
+                send_debounced(batch);
+            }
+        });
+    }
+
+    Ok(())
 }
 
-fn group(mut messages: Vec<String>, suffix: &str) -> (String, Option<Vec<String>>) {
-    if messages.len() == 1 {
-        (messages.remove(0), None)
-    } else {
-        let title = format!("{} {suffix}", messages.len());
+/// send anything from `source` still waiting on the debounce timer
+pub async fn flush_debounced(source: Option<&str>) {
+    let taken = with_debounced(source, Debounced::take).await;
 
-        if messages.len() < 7 {
-            (title, Some(messages))
-        } else {
-            // show counts instead of names
-            (title, None)
+    if let Some(batch) = taken {
+        send_debounced(batch);
+    }
+}
+
+fn send_friend_notification(
+    origin: EventOrigin,
+    timestamp: PrimitiveDateTime,
+    event: &MessageEvent,
+) {
+    let friends = &current_config().friends;
+    let (audio_event, kind, title) = match event {
+        MessageEvent::Join { .. } => (
+            AudioEvent::FriendJoin,
+            NotificationKind::Join,
+            &friends.join_title,
+        ),
+        MessageEvent::Leave(_) => (
+            AudioEvent::FriendLeave,
+            NotificationKind::Leave,
+            &friends.leave_title,
+        ),
+    };
+
+    let _ = audio::handle_event(&audio_event);
+    let title = title.replace("{name}", &event.name());
+    let body = match event {
+        MessageEvent::Join { player, .. } => describe_join(player, timestamp).into_iter().collect(),
+        MessageEvent::Leave(_) => Vec::new(),
+    };
+    send_notification(&Notification {
+        timestamp,
+        body,
+        world: origin.world,
+        source: origin.source,
+        ..Notification::new(kind, &title)
+    });
+}
+
+fn send_debounced(batch: DebouncedBatch) {
+    let DebouncedBatch {
+        timestamp,
+        origin,
+        events,
+    } = batch;
+
+    let config = current_config();
+    let mut notifies = Vec::new();
+
+    let (joins, leaves): (Vec<_>, Vec<_>) = events
+        .into_iter()
+        .partition(|event| matches!(event, MessageEvent::Join { .. }));
+
+    if !joins.is_empty() {
+        let _ = audio::handle_event(&AudioEvent::Join);
+        // only single joins have room for the history
+        let description = match joins.as_slice() {
+            [event] => describe_join(event.player(), timestamp),
+            _ => None,
+        };
+        let (title, mut body) = group(
+            joins.iter().map(MessageEvent::name).collect(),
+            "joined",
+            &config.grouping,
+        );
+        body.extend(description);
+        notifies.push(Notification {
+            timestamp,
+            body,
+            world: origin.world.clone(),
+            source: origin.source.clone(),
+            ..Notification::new(NotificationKind::Join, &title)
+        });
+    }
+
+    if !leaves.is_empty() {
+        let _ = audio::handle_event(&AudioEvent::Leave);
+        let (title, body) = group(
+            leaves.iter().map(MessageEvent::name).collect(),
+            "left",
+            &config.grouping,
+        );
+        notifies.push(Notification {
+            timestamp,
+            body,
+            world: origin.world.clone(),
+            source: origin.source.clone(),
+            ..Notification::new(NotificationKind::Leave, &title)
+        });
+    }
+
+    for notification in notifies {
+        send_notification(&notification);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{with_debounced, Debounced, MessageEvent, Notification, NotificationKind};
+    use crate::parser::event::Player;
+
+    #[test]
+    fn tags_titles_with_the_source() {
+        let mut notification = Notification::new(NotificationKind::Join, "Bob joined");
+        assert_eq!(notification.tagged_title(), "Bob joined");
+
+        notification.source = Some("alt".to_string());
+        assert_eq!(notification.tagged_title(), "[alt] Bob joined");
+    }
+
+    #[tokio::test]
+    async fn keeps_a_batch_per_source() {
+        let join = |name: &str| MessageEvent::Join {
+            player: Player::parse(name),
+            former_name: None,
+        };
+
+        for (source, name) in [("first", "Bob"), ("second", "Carol")] {
+            with_debounced(Some(source), |debounced| {
+                debounced.timestamp = Some(datetime!(2024-01-01 20:00:00));
+                debounced.events.push(join(name));
+            })
+            .await;
+        }
+
+        for (source, name) in [("first", "Bob"), ("second", "Carol")] {
+            let batch = with_debounced(Some(source), Debounced::take).await.unwrap();
+            assert_eq!(batch.origin.source.as_deref(), Some(source));
+            assert_eq!(batch.events, vec![join(name)]);
         }
     }
 }
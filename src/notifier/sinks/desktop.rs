@@ -0,0 +1,30 @@
+use std::thread;
+
+use anyhow::Result;
+
+use super::NotificationSink;
+use crate::notifier::Notification;
+
+/// Native desktop notifications, for when there's no headset
+pub struct DesktopSink;
+
+impl NotificationSink for DesktopSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let title = notification.tagged_title();
+        let body = notification.body.join("\n");
+
+        // talks to dbus on linux which can be slow
+        thread::spawn(move || {
+            if let Err(e) = notify_rust::Notification::new()
+                .appname("VRChat Join/Leave Notifier")
+                .summary(&title)
+                .body(&body)
+                .show()
+            {
+                eprintln!("desktop: {e:?}");
+            }
+        });
+
+        Ok(())
+    }
+}
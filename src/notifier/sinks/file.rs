@@ -0,0 +1,48 @@
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use super::NotificationSink;
+use crate::notifier::Notification;
+
+/// Appends a line per notification to a text file
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl NotificationSink for FileSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let mut text = format!("{} | ", notification.format_timestamp());
+        if let Some(world) = &notification.world {
+            write!(text, "{world} | ")?;
+        }
+        text.push_str(&notification.tagged_title());
+        for line in &notification.body {
+            write!(text, " | {line}")?;
+        }
+        text.push('\n');
+
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("opening {:?}", self.path))?;
+        f.write_all(text.as_bytes())?;
+
+        Ok(())
+    }
+}
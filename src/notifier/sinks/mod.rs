@@ -0,0 +1,311 @@
+pub mod desktop;
+pub mod file;
+pub mod stdout;
+pub mod webhook;
+
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{LazyLock, RwLock},
+};
+
+use anyhow::{bail, ensure, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use self::{desktop::DesktopSink, file::FileSink, stdout::StdoutSink, webhook::WebhookSink};
+use super::{Notification, NotificationKind};
+
+/// Somewhere notifications can be shown or recorded
+pub trait NotificationSink: Send + Sync {
+    /// Called from async code, slow work should be handed off to another
+    /// thread.
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Which notifications a sink gets
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SinkFilter {
+    /// `None` for every kind
+    pub kinds: Option<Vec<NotificationKind>>,
+}
+
+impl SinkFilter {
+    /// for sinks that are registered but turned off
+    #[must_use]
+    pub fn nothing() -> Self {
+        Self {
+            kinds: Some(Vec::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn allows(&self, notification: &Notification) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&notification.kind))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum SinkType {
+    Stdout,
+    /// toasts in the headset, registered once attached to `SteamVR`
+    #[value(help = "toasts in the headset")]
+    Steamvr,
+    Desktop,
+    File,
+    Webhook,
+}
+
+impl SinkType {
+    /// types that make no sense to have twice
+    #[must_use]
+    pub fn is_singleton(self) -> bool {
+        matches!(self, Self::Stdout | Self::Steamvr | Self::Desktop)
+    }
+}
+
+impl Display for SinkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Stdout => "stdout",
+            Self::Steamvr => "steamvr",
+            Self::Desktop => "desktop",
+            Self::File => "file",
+            Self::Webhook => "webhook",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A `[[sinks]]` entry in the config file
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SinkConfig {
+    #[serde(rename = "type")]
+    pub sink_type: SinkType,
+    /// every kind if unset
+    pub kinds: Option<Vec<NotificationKind>>,
+    /// for `file`
+    pub path: Option<PathBuf>,
+    /// for `webhook`
+    pub url: Option<String>,
+}
+
+impl SinkConfig {
+    #[must_use]
+    pub fn new(sink_type: SinkType) -> Self {
+        Self {
+            sink_type,
+            kinds: None,
+            path: None,
+            url: None,
+        }
+    }
+
+    #[must_use]
+    pub fn filter(&self) -> SinkFilter {
+        SinkFilter {
+            kinds: self.kinds.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.kinds.as_ref().is_some_and(Vec::is_empty) {
+            bail!("kinds is empty, nothing would be sent");
+        }
+
+        match self.sink_type {
+            SinkType::File => ensure!(self.path.is_some(), "path is required"),
+            _ => ensure!(self.path.is_none(), "path is only for file sinks"),
+        }
+
+        match (self.sink_type, &self.url) {
+            (SinkType::Webhook, None) => bail!("url is required"),
+            (SinkType::Webhook, Some(url)) => ensure!(
+                url.starts_with("http://") || url.starts_with("https://"),
+                "url {url:?} should start with http:// or https://"
+            ),
+            (_, Some(_)) => bail!("url is only for webhook sinks"),
+            (_, None) => {}
+        }
+
+        Ok(())
+    }
+
+    /// registry name, numbered for types there can be several of
+    #[must_use]
+    pub fn name(&self, index: usize) -> String {
+        if self.sink_type.is_singleton() {
+            self.sink_type.to_string()
+        } else {
+            format!("{}#{index}", self.sink_type)
+        }
+    }
+}
+
+/// Registers the configured sinks. `steamvr` is registered by the vr runtime
+/// once it's attached, so only its filter is updated here.
+pub fn register_configured_sinks(configs: &[SinkConfig]) {
+    if !configs
+        .iter()
+        .any(|config| config.sink_type == SinkType::Steamvr)
+    {
+        set_sink_filter(&SinkType::Steamvr.to_string(), SinkFilter::nothing());
+    }
+
+    for (index, config) in configs.iter().enumerate() {
+        let name = config.name(index);
+        let filter = config.filter();
+        match (config.sink_type, &config.path, &config.url) {
+            (SinkType::Stdout, _, _) => register_sink(&name, StdoutSink, filter),
+            (SinkType::Steamvr, _, _) => set_sink_filter(&name, filter),
+            (SinkType::Desktop, _, _) => register_sink(&name, DesktopSink, filter),
+            (SinkType::File, Some(path), _) => register_sink(&name, FileSink::new(path), filter),
+            (SinkType::Webhook, _, Some(url)) => {
+                register_sink(&name, WebhookSink::new(url), filter);
+            }
+            // validated already
+            (SinkType::File | SinkType::Webhook, _, _) => {}
+        }
+    }
+}
+
+/// Undoes `register_configured_sinks`, leaving `steamvr` to the vr runtime.
+pub fn unregister_configured_sinks(configs: &[SinkConfig]) {
+    for (index, config) in configs.iter().enumerate() {
+        if config.sink_type != SinkType::Steamvr {
+            unregister_sink(&config.name(index));
+        }
+    }
+}
+
+struct RegisteredSink {
+    name: String,
+    sink: Box<dyn NotificationSink>,
+    filter: SinkFilter,
+}
+
+static SINKS: LazyLock<RwLock<Vec<RegisteredSink>>> = LazyLock::new(RwLock::default);
+
+/// replaces any sink with the same name
+pub fn register_sink<S: NotificationSink + 'static>(name: &str, sink: S, filter: SinkFilter) {
+    let mut sinks = SINKS.write().unwrap();
+    sinks.retain(|registered| registered.name != name);
+    sinks.push(RegisteredSink {
+        name: name.to_string(),
+        sink: Box::new(sink),
+        filter,
+    });
+}
+
+pub fn unregister_sink(name: &str) {
+    let mut sinks = SINKS.write().unwrap();
+    sinks.retain(|registered| registered.name != name);
+}
+
+/// does nothing if there's no sink with this name
+pub fn set_sink_filter(name: &str, filter: SinkFilter) {
+    let mut sinks = SINKS.write().unwrap();
+    if let Some(registered) = sinks.iter_mut().find(|registered| registered.name == name) {
+        registered.filter = filter;
+    }
+}
+
+pub(super) fn dispatch(notification: &Notification) {
+    let sinks = SINKS.read().unwrap();
+    for registered in sinks.iter() {
+        if !registered.filter.allows(notification) {
+            continue;
+        }
+
+        if let Err(e) = registered.sink.send(notification) {
+            eprintln!("{}: {e:?}", registered.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::Result;
+
+    use super::{dispatch, register_sink, unregister_sink, NotificationSink, SinkFilter};
+    use crate::notifier::{Notification, NotificationKind};
+
+    /// other tests dispatch too, only theirs are kept
+    const TITLE_PREFIX: &str = "sinks test";
+
+    #[derive(Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    impl RecordingSink {
+        fn titles(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl NotificationSink for RecordingSink {
+        fn send(&self, notification: &Notification) -> Result<()> {
+            if notification.title.starts_with(TITLE_PREFIX) {
+                self.0.lock().unwrap().push(notification.title.clone());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn filters_by_kind() {
+        let join = Notification::new(NotificationKind::Join, "Bob joined");
+        let leave = Notification::new(NotificationKind::Leave, "Bob left");
+
+        assert!(SinkFilter::default().allows(&join));
+        assert!(!SinkFilter::nothing().allows(&join));
+
+        let leaves = SinkFilter {
+            kinds: Some(vec![NotificationKind::Leave]),
+        };
+        assert!(!leaves.allows(&join));
+        assert!(leaves.allows(&leave));
+    }
+
+    #[test]
+    fn dispatches_to_registered_sinks() {
+        let replaced = RecordingSink::default();
+        let all = RecordingSink::default();
+        let leaves = RecordingSink::default();
+
+        register_sink("sinks-test-all", replaced.clone(), SinkFilter::default());
+        register_sink("sinks-test-all", all.clone(), SinkFilter::default());
+        register_sink(
+            "sinks-test-leaves",
+            leaves.clone(),
+            SinkFilter {
+                kinds: Some(vec![NotificationKind::Leave]),
+            },
+        );
+
+        dispatch(&Notification::new(
+            NotificationKind::Join,
+            "sinks test joined",
+        ));
+        dispatch(&Notification::new(
+            NotificationKind::Leave,
+            "sinks test left",
+        ));
+
+        unregister_sink("sinks-test-all");
+        unregister_sink("sinks-test-leaves");
+        dispatch(&Notification::new(
+            NotificationKind::Leave,
+            "sinks test after unregistering",
+        ));
+
+        assert_eq!(replaced.titles(), Vec::<String>::new());
+        assert_eq!(all.titles(), vec!["sinks test joined", "sinks test left"]);
+        assert_eq!(leaves.titles(), vec!["sinks test left"]);
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use deunicode::deunicode;
+
+use super::NotificationSink;
+use crate::notifier::Notification;
+
+pub struct StdoutSink;
+
+impl NotificationSink for StdoutSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        // windows consoles don't do unicode well
+        let date = notification.format_timestamp();
+        let title = deunicode(&notification.tagged_title());
+        if let Some(world) = &notification.world {
+            println!("{date} | {} | {title}", deunicode(&world.to_string()));
+        } else {
+            println!("{date} | {title}");
+        }
+
+        for line in &notification.body {
+            println!("    {}", deunicode(line));
+        }
+
+        Ok(())
+    }
+}
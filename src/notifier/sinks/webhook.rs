@@ -0,0 +1,56 @@
+use std::thread;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::NotificationSink;
+use crate::{
+    notifier::{Notification, NotificationKind},
+    parser::world::WorldInstance,
+};
+
+/// POSTs each notification as json
+pub struct WebhookSink {
+    url: String,
+}
+
+impl WebhookSink {
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookPayload {
+    timestamp: String,
+    kind: NotificationKind,
+    title: String,
+    body: Vec<String>,
+    world: Option<WorldInstance>,
+    source: Option<String>,
+}
+
+impl NotificationSink for WebhookSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let url = self.url.clone();
+        let payload = WebhookPayload {
+            timestamp: notification.format_timestamp(),
+            kind: notification.kind,
+            title: notification.title.clone(),
+            body: notification.body.clone(),
+            world: notification.world.clone(),
+            source: notification.source.clone(),
+        };
+
+        thread::spawn(move || {
+            if let Err(e) = ureq::post(&url).send_json(&payload) {
+                eprintln!("webhook: {e:?}");
+            }
+        });
+
+        Ok(())
+    }
+}
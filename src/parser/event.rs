@@ -0,0 +1,257 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+
+use super::{
+    line::{LogLevel, LogLine},
+    world::WorldInstance,
+};
+
+// [Behaviour] Joining wrld_...:12345~region(eu)
+const JOINING_WORLD_LOG_PREFIX: &str = "[Behaviour] Joining ";
+// [Behaviour] Entering Room: World Name
+const ENTERING_ROOM_LOG_PREFIX: &str = "[Behaviour] Entering Room: ";
+// [Behaviour] Joining or Creating Room: World Name
+const JOINING_OR_CREATING_ROOM_LOG_PREFIX: &str = "[Behaviour] Joining or Creating Room: ";
+const ENTERING_WORLD_LOG: &str = "[Behaviour] Entering world";
+const FINISHED_ENTERING_WORLD_LOG: &str = "[Behaviour] Finished entering world.";
+const ON_LEFT_ROOM_LOG: &str = "[Behaviour] OnLeftRoom";
+// TODO can we know if the game is closed if not getting this? yes we can! openvr close event!
+const APPLICATION_QUIT_LOG_PREFIX: &str = "VRCApplication: OnApplicationQuit at ";
+
+// [Behaviour] OnPlayerJoined SpiralP (usr_...)
+const PLAYER_JOINED_LOG_PREFIX: &str = "[Behaviour] OnPlayerJoined ";
+// [Behaviour] OnPlayerJoinComplete SpiralP
+const PLAYER_JOIN_COMPLETE_LOG_PREFIX: &str = "[Behaviour] OnPlayerJoinComplete ";
+
+// [Behaviour] OnPlayerLeft SpiralP (usr_...)
+const PLAYER_LEFT_LOG_PREFIX: &str = "[Behaviour] OnPlayerLeft ";
+const UNREGISTERING_LOG_PREFIX: &str = "[Behaviour] Unregistering ";
+
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    /// `usr_...`, missing in logs from older game versions
+    pub user_id: Option<String>,
+    pub display_name: String,
+}
+
+impl Player {
+    /// `SpiralP (usr_...)` or just `SpiralP`
+    #[must_use]
+    pub fn parse(name_and_uid: &str) -> Self {
+        if let Some((display_name, user_id)) = name_and_uid
+            .strip_suffix(')')
+            .and_then(|rest| rest.rsplit_once(" ("))
+            .filter(|(_, user_id)| user_id.starts_with("usr_"))
+        {
+            Self {
+                user_id: Some(user_id.to_string()),
+                display_name: display_name.to_string(),
+            }
+        } else {
+            Self {
+                user_id: None,
+                display_name: name_and_uid.to_string(),
+            }
+        }
+    }
+
+    /// stable identifier, falling back to the display name when there's no user id
+    #[must_use]
+    pub fn key(&self) -> &str {
+        self.user_id.as_deref().unwrap_or(&self.display_name)
+    }
+}
+
+impl Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParsedEvent {
+    JoiningWorld {
+        timestamp: PrimitiveDateTime,
+        instance: WorldInstance,
+    },
+    EnteringRoom {
+        timestamp: PrimitiveDateTime,
+        world_name: String,
+    },
+    EnteringWorld {
+        timestamp: PrimitiveDateTime,
+    },
+    FinishedEnteringWorld {
+        timestamp: PrimitiveDateTime,
+    },
+    LeftRoom {
+        timestamp: PrimitiveDateTime,
+    },
+    ApplicationQuit {
+        timestamp: PrimitiveDateTime,
+    },
+    PlayerJoined {
+        timestamp: PrimitiveDateTime,
+        player: Player,
+    },
+    /// always follows `PlayerJoined` for the same player, without a user id
+    PlayerJoinComplete {
+        timestamp: PrimitiveDateTime,
+        display_name: String,
+    },
+    PlayerLeft {
+        timestamp: PrimitiveDateTime,
+        player: Player,
+    },
+    /// always follows `PlayerLeft` for the same player, without a user id
+    Unregistering {
+        timestamp: PrimitiveDateTime,
+        display_name: String,
+    },
+}
+
+impl ParsedEvent {
+    /// `None` for lines that aren't one of the events, including warnings and
+    /// errors that happen to quote them
+    #[must_use]
+    pub fn from_line(line: &LogLine) -> Option<Self> {
+        // newer game versions log these at debug level
+        if !matches!(line.level, LogLevel::Debug | LogLevel::Log) {
+            return None;
+        }
+
+        let timestamp = line.timestamp;
+        let message = line.message.as_str();
+
+        let event = if let Some(instance) = message
+            .strip_prefix(JOINING_WORLD_LOG_PREFIX)
+            .filter(|instance| instance.starts_with("wrld_"))
+        {
+            Self::JoiningWorld {
+                timestamp,
+                instance: instance.parse().ok()?,
+            }
+        } else if let Some(world_name) = message
+            .strip_prefix(ENTERING_ROOM_LOG_PREFIX)
+            .or_else(|| message.strip_prefix(JOINING_OR_CREATING_ROOM_LOG_PREFIX))
+        {
+            Self::EnteringRoom {
+                timestamp,
+                world_name: world_name.to_string(),
+            }
+        } else if message == ENTERING_WORLD_LOG {
+            Self::EnteringWorld { timestamp }
+        } else if message == FINISHED_ENTERING_WORLD_LOG {
+            Self::FinishedEnteringWorld { timestamp }
+        } else if message == ON_LEFT_ROOM_LOG {
+            Self::LeftRoom { timestamp }
+        } else if message.starts_with(APPLICATION_QUIT_LOG_PREFIX) {
+            Self::ApplicationQuit { timestamp }
+        } else if let Some(name_and_uid) = message.strip_prefix(PLAYER_JOINED_LOG_PREFIX) {
+            Self::PlayerJoined {
+                timestamp,
+                player: Player::parse(name_and_uid),
+            }
+        } else if let Some(name) = message.strip_prefix(PLAYER_JOIN_COMPLETE_LOG_PREFIX) {
+            Self::PlayerJoinComplete {
+                timestamp,
+                display_name: name.to_string(),
+            }
+        } else if let Some(name_and_uid) = message.strip_prefix(PLAYER_LEFT_LOG_PREFIX) {
+            Self::PlayerLeft {
+                timestamp,
+                player: Player::parse(name_and_uid),
+            }
+        } else if let Some(name) = message.strip_prefix(UNREGISTERING_LOG_PREFIX) {
+            Self::Unregistering {
+                timestamp,
+                display_name: name.to_string(),
+            }
+        } else {
+            return None;
+        };
+
+        Some(event)
+    }
+
+    #[must_use]
+    pub fn timestamp(&self) -> PrimitiveDateTime {
+        match self {
+            Self::JoiningWorld { timestamp, .. }
+            | Self::EnteringRoom { timestamp, .. }
+            | Self::EnteringWorld { timestamp }
+            | Self::FinishedEnteringWorld { timestamp }
+            | Self::LeftRoom { timestamp }
+            | Self::ApplicationQuit { timestamp }
+            | Self::PlayerJoined { timestamp, .. }
+            | Self::PlayerJoinComplete { timestamp, .. }
+            | Self::PlayerLeft { timestamp, .. }
+            | Self::Unregistering { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{ParsedEvent, Player};
+
+    fn parse(line: &str) -> Option<ParsedEvent> {
+        ParsedEvent::from_line(&line.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_players() {
+        let Some(ParsedEvent::PlayerJoined { player, .. }) =
+            parse("2022.07.27 15:26:35 Log        -  [Behaviour] OnPlayerJoined SpiralP (usr_a)")
+        else {
+            panic!("not a join");
+        };
+        assert_eq!(
+            player,
+            Player {
+                user_id: Some("usr_a".to_string()),
+                display_name: "SpiralP".to_string(),
+            }
+        );
+
+        let Some(ParsedEvent::PlayerLeft { player, .. }) =
+            parse("2022.07.27 15:26:35 Log        -  [Behaviour] OnPlayerLeft a - b (c) (usr_b)")
+        else {
+            panic!("not a leave");
+        };
+        assert_eq!(player.display_name, "a - b (c)");
+        assert_eq!(player.user_id.as_deref(), Some("usr_b"));
+
+        // older game versions
+        assert_eq!(
+            Player::parse("SpiralP (old)"),
+            Player {
+                user_id: None,
+                display_name: "SpiralP (old)".to_string(),
+            }
+        );
+        assert_eq!(Player::parse("SpiralP").key(), "SpiralP");
+    }
+
+    #[test]
+    fn only_reads_events_from_log_and_debug_lines() {
+        for level in ["Log", "Debug"] {
+            assert!(parse(&format!(
+                "2024.01.01 20:00:00 {level}        -  [Behaviour] OnPlayerLeft SpiralP (usr_a)"
+            ))
+            .is_some_and(|event| event.timestamp() == datetime!(2024-01-01 20:00:00)));
+        }
+        assert_eq!(
+            parse("2024.01.01 20:00:00 Error      -  [Behaviour] OnPlayerLeft SpiralP (usr_a)"),
+            None
+        );
+        assert_eq!(
+            parse("2024.01.01 20:00:00 Log        -  [Behaviour] Something else"),
+            None
+        );
+    }
+}
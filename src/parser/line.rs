@@ -0,0 +1,113 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+use time::{macros::format_description, PrimitiveDateTime};
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+pub enum LogLevel {
+    Debug,
+    Log,
+    Warning,
+    Error,
+    Exception,
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "Debug" => Self::Debug,
+            "Log" => Self::Log,
+            "Warning" => Self::Warning,
+            "Error" => Self::Error,
+            "Exception" => Self::Exception,
+            _ => bail!("unknown log level {s:?}"),
+        })
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Debug => "Debug",
+            Self::Log => "Log",
+            Self::Warning => "Warning",
+            Self::Error => "Error",
+            Self::Exception => "Exception",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A single timestamped line from `output_log_*.txt`
+///
+/// Continuation lines (stack traces, multi-line messages) don't start with a
+/// timestamp and fail to parse.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LogLine {
+    /// local time of the machine that wrote the log
+    pub timestamp: PrimitiveDateTime,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+impl FromStr for LogLine {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        // 2022.07.27 15:26:35 Log        -  [Behaviour] OnPlayerJoined SpiralP
+        let (info, message) = line.split_once('-').context("no separator")?;
+
+        let info = info.trim();
+        // date and time are the first 2 space separated parts
+        let time_end = info
+            .match_indices(' ')
+            .nth(1)
+            .map(|(i, _)| i)
+            .context("no level")?;
+        let (timestamp, level) = info.split_at(time_end);
+
+        let timestamp = PrimitiveDateTime::parse(
+            timestamp,
+            format_description!("[year].[month].[day] [hour]:[minute]:[second]"),
+        )
+        .with_context(|| format!("parsing timestamp {timestamp:?}"))?;
+        let level = level.trim().parse()?;
+
+        Ok(Self {
+            timestamp,
+            level,
+            message: message.trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{LogLevel, LogLine};
+
+    #[test]
+    fn parses_lines() {
+        let line: LogLine =
+            "2022.07.27 15:26:35 Log        -  [Behaviour] OnPlayerJoined Some-Name (usr_a)"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            line,
+            LogLine {
+                timestamp: datetime!(2022-07-27 15:26:35),
+                level: LogLevel::Log,
+                message: "[Behaviour] OnPlayerJoined Some-Name (usr_a)".to_string(),
+            }
+        );
+
+        // stack trace under an exception
+        assert!("  at VRC.Core.ApiModel.Fetch () [0x00000] in <00000>:0"
+            .parse::<LogLine>()
+            .is_err());
+        assert!("".parse::<LogLine>().is_err());
+    }
+}
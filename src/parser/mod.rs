@@ -1,111 +1,313 @@
 #![allow(clippy::unused_async)]
 
+pub mod event;
+pub mod line;
+pub mod reader;
+#[cfg(target_os = "linux")]
+pub mod steam;
 pub mod utils;
+pub mod world;
 
 use std::{
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{Context, Result};
-use tokio::{
-    fs::File,
-    io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
-    time::MissedTickBehavior,
-};
-
-use crate::notifier::{debounced_notify, MessageEvent};
+use anyhow::Result;
+use time::PrimitiveDateTime;
+use tokio::time::{Instant, MissedTickBehavior};
 
-const ENTERING_WORLD_LOG: &str = "[Behaviour] Entering world";
-const FINISHED_ENTERING_WORLD_LOG: &str = "[Behaviour] Finished entering world.";
-const ON_LEFT_ROOM_LOG: &str = "[Behaviour] OnLeftRoom";
-// TODO can we know if the game is closed if not getting this? yes we can! openvr close event!
-const APPLICATION_QUIT_LOG_PREFIX: &str = "VRCApplication: OnApplicationQuit at ";
+use self::{
+    event::{ParsedEvent, Player},
+    line::LogLine,
+    reader::LogReader,
+    world::WorldInstance,
+};
+use crate::{
+    cli::verbosity,
+    config::{resume::ResumeState, settings::current_config},
+    history::{self, HistoryKind, HistoryRecord},
+    log_watcher::fs_events::FsEvents,
+    notifier::{
+        debounced_notify, flush_debounced, send_notification, EventOrigin, MessageEvent,
+        Notification, NotificationKind,
+    },
+    roster::{current_roster, current_world, roster_handle, Roster, SharedRoster},
+    session::Session,
+};
 
-// [Behaviour] OnPlayerJoined SpiralP (usr_...)
-const PLAYER_JOINED_LOG_PREFIX: &str = "[Behaviour] OnPlayerJoined ";
-// [Behaviour] OnPlayerJoinComplete SpiralP
-const PLAYER_JOIN_COMPLETE_LOG_PREFIX: &str = "[Behaviour] OnPlayerJoinComplete ";
+const RESUME_SAVE_INTERVAL: Duration = Duration::from_secs(5);
 
-// [Behaviour] OnPlayerLeft SpiralP (usr_...)
-const PLAYER_LEFT_LOG_PREFIX: &str = "[Behaviour] OnPlayerLeft ";
-const UNREGISTERING_LOG_PREFIX: &str = "[Behaviour] Unregistering ";
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum StartPosition {
+    /// read everything already in the file
+    Start,
+    /// only read lines written from now on
+    End,
+    /// restore the roster from a previous run and catch up on what was
+    /// written since, without notifying
+    Resume(ResumeState),
+}
 
 pub struct LogParser {
     log_path: PathBuf,
+    /// name of the log source, `None` for the main one
+    source: Option<String>,
+    /// who's in the instance according to this log, also seen by
+    /// `current_roster`
+    roster: SharedRoster,
+    /// between `EnteringWorld` and `FinishedEnteringWorld`, joins are the
+    /// players already in the instance
+    loading_world: bool,
+    /// processing lines written while we weren't running
+    catching_up: bool,
+    /// off for replays, which would record old sessions again
+    record_history: bool,
+    /// the instance visit being summarized
+    session: Option<Session>,
 }
 
 impl LogParser {
-    pub fn new(log_path: &Path) -> Result<Self> {
+    pub fn new(log_path: &Path, source: Option<String>) -> Result<Self> {
         Ok(Self {
             log_path: log_path.to_owned(),
+            roster: roster_handle(source.as_deref()),
+            source,
+            loading_world: false,
+            catching_up: false,
+            record_history: true,
+            session: None,
         })
     }
 
-    pub async fn read_loop(mut self) -> Result<()> {
-        let mut f = File::open(&self.log_path).await?;
-        f.seek(std::io::SeekFrom::End(0)).await?;
-        let f = BufReader::new(f);
-        let mut lines = f.lines();
+    pub async fn read_loop(mut self, start_position: StartPosition) -> Result<()> {
+        let offset = match start_position {
+            StartPosition::Start => {
+                self.reset_roster();
+                Some(0)
+            }
+            StartPosition::End => {
+                self.reset_roster();
+                None
+            }
+            StartPosition::Resume(resume_state) => {
+                let roster = resume_state.to_roster();
+                self.with_roster(|current| *current = roster);
+                self.catching_up = true;
+                Some(resume_state.offset)
+            }
+        };
+
+        let mut reader = LogReader::open(&self.log_path, offset).await?;
+        let mut saved_offset = None;
+        let mut saved_at = Instant::now();
+
+        let mut fs_events = FsEvents::modified(&self.log_path);
 
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        // fallback for when fs events are missed or unsupported
+        let mut interval = tokio::time::interval(current_config().poll.log_interval());
         interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {},
+                () = fs_events.changed() => {},
+            }
 
-            while let Some(line) = lines.next_line().await? {
+            while let Some(line) = reader.next_line().await? {
                 if line.is_empty() {
                     continue;
                 }
 
-                if let Err(_e) = self.handle_line(&line).await {
-                    // eprintln!("{e:?}: {line:?}");
+                if let Err(e) = self.handle_line(&line).await {
+                    if verbosity() >= 2 {
+                        eprintln!("{e:?}: {line:?}");
+                    }
+                }
+            }
+
+            if self.catching_up {
+                self.catching_up = false;
+                let status = self.with_roster(|roster| {
+                    if roster.is_empty() {
+                        "not in an instance".to_string()
+                    } else {
+                        format!("{} players in instance", roster.len())
+                    }
+                });
+                println!("{}caught up, {status}", self.tag());
+            }
+
+            if saved_offset != Some(reader.offset())
+                && (saved_offset.is_none() || saved_at.elapsed() >= RESUME_SAVE_INTERVAL)
+            {
+                saved_offset = Some(reader.offset());
+                saved_at = Instant::now();
+
+                let resume_state = ResumeState::new(
+                    self.log_path.clone(),
+                    reader.offset(),
+                    &current_roster(self.source.as_deref()),
+                );
+                if let Err(e) = resume_state.save(self.source.as_deref()).await {
+                    eprintln!("saving resume state: {e:?}");
                 }
             }
         }
     }
 
+    /// Process the whole file once from the start.
+    ///
+    /// With `speed` set, sleeps between lines to follow the original timestamps,
+    /// `2.0` being twice as fast. Otherwise runs as fast as possible.
+    pub async fn replay(mut self, speed: Option<f64>) -> Result<()> {
+        self.reset_roster();
+        self.record_history = false;
+
+        let mut reader = LogReader::open(&self.log_path, Some(0)).await?;
+
+        let mut last_timestamp = None;
+        while let Some(line) = match reader.next_line().await? {
+            Some(line) => Some(line),
+            None => reader.take_partial_line(),
+        } {
+            let Some(event) = line
+                .parse::<LogLine>()
+                .ok()
+                .as_ref()
+                .and_then(ParsedEvent::from_line)
+            else {
+                continue;
+            };
+
+            if let Some(speed) = speed {
+                let timestamp = event.timestamp();
+                if let Some(last_timestamp) = last_timestamp {
+                    let delta = timestamp - last_timestamp;
+                    if let Ok(delta) = Duration::try_from(delta) {
+                        tokio::time::sleep(delta.div_f64(speed)).await;
+                    }
+                }
+                last_timestamp = Some(timestamp);
+            }
+
+            if let Err(e) = self.handle_event(event).await {
+                if verbosity() >= 2 {
+                    eprintln!("{e:?}: {line:?}");
+                }
+            }
+        }
+
+        flush_debounced(self.source.as_deref()).await;
+
+        Ok(())
+    }
+
     async fn handle_line(&mut self, line: &str) -> Result<()> {
-        // 2022.07.27 15:26:35 Log        -  [Behaviour] OnPlayerJoined SpiralP
-        let mut parts = line.splitn(2, '-');
-        let _info = parts.next().context("no first part")?;
-        let message = parts.next().context("no second part")?.trim();
-
-        if message == ENTERING_WORLD_LOG {
-            self.handle_entering_world().await?;
-            self.handle_world_state_change().await?;
-        } else if message == FINISHED_ENTERING_WORLD_LOG {
-            self.handle_world_state_change().await?;
-        } else if message == ON_LEFT_ROOM_LOG {
-            self.handle_left_room().await?;
-            self.handle_world_state_change().await?;
-        } else if message.starts_with(APPLICATION_QUIT_LOG_PREFIX) {
-            self.handle_world_state_change().await?;
-        } else if let Some(name_and_uid) = message.strip_prefix(PLAYER_JOINED_LOG_PREFIX) {
-            let name = name_and_uid
-                .split_once(" (usr_")
-                .map_or(name_and_uid, |(name, _)| name);
-            self.handle_player_join(name).await?;
-        } else if let Some(name) = message.strip_prefix(PLAYER_JOIN_COMPLETE_LOG_PREFIX) {
-            self.handle_player_join(name).await?;
-        } else if let Some(name_and_uid) = message.strip_prefix(PLAYER_LEFT_LOG_PREFIX) {
-            let name = name_and_uid
-                .split_once(" (usr_")
-                .map_or(name_and_uid, |(name, _)| name);
-            self.handle_player_leave(name).await?;
-        } else if let Some(name) = message.strip_prefix(UNREGISTERING_LOG_PREFIX) {
-            self.handle_player_leave(name).await?;
+        let line = line.parse::<LogLine>()?;
+        if let Some(event) = ParsedEvent::from_line(&line) {
+            self.handle_event(event).await?;
         }
 
         Ok(())
     }
 
+    async fn handle_event(&mut self, event: ParsedEvent) -> Result<()> {
+        if verbosity() >= 1 {
+            println!("{event:?}");
+        }
+
+        match event {
+            ParsedEvent::JoiningWorld {
+                timestamp,
+                instance,
+            } => {
+                // normally already finished by leaving the room
+                self.finish_session(timestamp).await;
+                self.handle_joining_world(instance).await?;
+            }
+            ParsedEvent::EnteringRoom { world_name, .. } => {
+                self.with_roster(|roster| roster.set_world_name(&world_name));
+            }
+            ParsedEvent::EnteringWorld { .. } => {
+                self.handle_entering_world().await?;
+                self.handle_world_state_change().await?;
+            }
+            ParsedEvent::FinishedEnteringWorld { timestamp } => {
+                self.handle_finished_entering_world(timestamp).await?;
+                self.handle_world_state_change().await?;
+            }
+            ParsedEvent::LeftRoom { timestamp } => {
+                self.finish_session(timestamp).await;
+                self.handle_left_room().await?;
+                self.handle_world_state_change().await?;
+            }
+            ParsedEvent::ApplicationQuit { timestamp } => {
+                self.finish_session(timestamp).await;
+                self.handle_left_room().await?;
+                self.with_roster(|roster| roster.set_world(None));
+                self.handle_world_state_change().await?;
+            }
+            ParsedEvent::PlayerJoined { timestamp, player } => {
+                self.handle_player_join(timestamp, player).await?;
+            }
+            ParsedEvent::PlayerLeft { timestamp, player } => {
+                self.handle_player_leave(timestamp, player).await?;
+            }
+            // these carry no user id and always follow the events above
+            ParsedEvent::PlayerJoinComplete { .. } | ParsedEvent::Unregistering { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    async fn handle_joining_world(&mut self, instance: WorldInstance) -> Result<()> {
+        self.with_roster(|roster| {
+            roster.clear();
+            roster.set_world(Some(instance));
+        });
+        Ok(())
+    }
+
     async fn handle_entering_world(&mut self) -> Result<()> {
+        self.loading_world = true;
+        self.with_roster(Roster::clear);
+        Ok(())
+    }
+
+    async fn handle_finished_entering_world(&mut self, timestamp: PrimitiveDateTime) -> Result<()> {
+        if !self.loading_world {
+            return Ok(());
+        }
+        self.loading_world = false;
+
+        let (world, count) = self.with_roster(|roster| (roster.world().cloned(), roster.len()));
+        self.session = Some(Session::new(world.clone(), timestamp, count));
+
+        if self.catching_up {
+            return Ok(());
+        }
+
+        let title = format!(
+            "Joined {} with {count} player{}",
+            world
+                .as_ref()
+                .map_or_else(|| "instance".to_string(), ToString::to_string),
+            if count == 1 { "" } else { "s" }
+        );
+        send_notification(&Notification {
+            timestamp,
+            world,
+            source: self.source.clone(),
+            ..Notification::new(NotificationKind::Instance, &title)
+        });
+
         Ok(())
     }
 
     async fn handle_left_room(&mut self) -> Result<()> {
+        self.loading_world = false;
+        self.with_roster(Roster::clear);
         Ok(())
     }
 
@@ -113,13 +315,183 @@ impl LogParser {
         Ok(())
     }
 
-    async fn handle_player_join(&mut self, name: &str) -> Result<()> {
-        debounced_notify(MessageEvent::Join(name.to_owned())).await?;
+    async fn handle_player_join(
+        &mut self,
+        timestamp: PrimitiveDateTime,
+        player: Player,
+    ) -> Result<()> {
+        if !self.with_roster(|roster| roster.join(player.clone(), timestamp)) {
+            return Ok(());
+        }
+        // before recording the join under the new name
+        let former_name = if self.record_history {
+            history::check_name_change(&player, timestamp)
+        } else {
+            None
+        };
+        self.record(HistoryKind::Join, timestamp, &player);
+
+        let count = self.with_roster(|roster| roster.len());
+        if let Some(session) = self.session.as_mut().filter(|_| !self.loading_world) {
+            session.join(&player, count);
+        }
+
+        if self.loading_world || self.catching_up {
+            return Ok(());
+        }
+
+        debounced_notify(
+            self.origin(),
+            timestamp,
+            MessageEvent::Join {
+                player,
+                former_name,
+            },
+        )
+        .await?;
         Ok(())
     }
 
-    async fn handle_player_leave(&mut self, name: &str) -> Result<()> {
-        debounced_notify(MessageEvent::Leave(name.to_owned())).await?;
+    async fn handle_player_leave(
+        &mut self,
+        timestamp: PrimitiveDateTime,
+        player: Player,
+    ) -> Result<()> {
+        self.with_roster(|roster| roster.leave(&player));
+        self.record(HistoryKind::Leave, timestamp, &player);
+
+        if let Some(session) = self.session.as_mut().filter(|_| !self.loading_world) {
+            session.leave(&player);
+        }
+
+        if self.loading_world || self.catching_up {
+            return Ok(());
+        }
+
+        debounced_notify(self.origin(), timestamp, MessageEvent::Leave(player)).await?;
         Ok(())
     }
+
+    async fn finish_session(&mut self, timestamp: PrimitiveDateTime) {
+        if let Some(session) = self.session.take() {
+            // so the last leaves show up before the summary
+            flush_debounced(self.source.as_deref()).await;
+            session.finish(timestamp, self.catching_up, self.source.as_deref());
+        }
+    }
+
+    fn record(&self, kind: HistoryKind, timestamp: PrimitiveDateTime, player: &Player) {
+        if !self.record_history {
+            return;
+        }
+
+        history::record(&HistoryRecord {
+            kind,
+            timestamp,
+            player: player.clone(),
+            world: current_world(self.source.as_deref()),
+        });
+    }
+
+    fn origin(&self) -> EventOrigin {
+        EventOrigin {
+            source: self.source.clone(),
+            world: current_world(self.source.as_deref()),
+        }
+    }
+
+    fn with_roster<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Roster) -> R,
+    {
+        let mut roster = self.roster.lock().unwrap();
+        f(&mut roster)
+    }
+
+    fn reset_roster(&self) {
+        self.with_roster(|roster| *roster = Roster::new());
+    }
+
+    /// `[source] ` to start terminal output with, empty for the main source
+    fn tag(&self) -> String {
+        self.source
+            .as_ref()
+            .map_or_else(String::new, |source| format!("[{source}] "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use anyhow::Result;
+
+    use super::LogParser;
+    use crate::notifier::{
+        flush_debounced,
+        sinks::{register_sink, unregister_sink, NotificationSink, SinkFilter},
+        Notification, NotificationKind,
+    };
+
+    /// other tests notify too, so they're told apart by source
+    const SOURCE: &str = "parser-test";
+
+    #[derive(Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<(NotificationKind, String)>>>);
+
+    impl NotificationSink for RecordingSink {
+        fn send(&self, notification: &Notification) -> Result<()> {
+            if notification.source.as_deref() == Some(SOURCE) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((notification.kind, notification.title.clone()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn summarizes_players_already_in_the_instance() {
+        let sink = RecordingSink::default();
+        register_sink(SOURCE, sink.clone(), SinkFilter::default());
+
+        let mut parser =
+            LogParser::new(Path::new("output_log.txt"), Some(SOURCE.to_string())).unwrap();
+        parser.record_history = false;
+
+        for (time, message) in [
+            ("20:00:00", "Joining wrld_abc:12345~region(eu)"),
+            ("20:00:01", "Entering Room: Test World"),
+            ("20:00:02", "Entering world"),
+            ("20:00:03", "OnPlayerJoined Me"),
+            ("20:00:03", "OnPlayerJoined Alice"),
+            ("20:00:03", "OnPlayerJoined Bob"),
+            ("20:00:04", "Finished entering world."),
+            ("20:05:00", "OnPlayerJoined Carol"),
+        ] {
+            parser
+                .handle_line(&format!(
+                    "2024.01.01 {time} Log        -  [Behaviour] {message}"
+                ))
+                .await
+                .unwrap();
+        }
+        flush_debounced(Some(SOURCE)).await;
+        unregister_sink(SOURCE);
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                (
+                    NotificationKind::Instance,
+                    "Joined Test World with 3 players".to_string()
+                ),
+                (NotificationKind::Join, "Carol joined".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use anyhow::Result;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
+};
+
+/// Reads complete lines from a log that's still being written, keeping track
+/// of the byte offset after the last complete line.
+pub struct LogReader {
+    reader: BufReader<File>,
+    offset: u64,
+    /// start of a line that hasn't been fully written yet
+    partial: Vec<u8>,
+}
+
+impl LogReader {
+    pub async fn open(log_path: &Path, offset: Option<u64>) -> Result<Self> {
+        let mut f = File::open(log_path).await?;
+        let offset = match offset {
+            Some(offset) => f.seek(std::io::SeekFrom::Start(offset)).await?,
+            None => f.seek(std::io::SeekFrom::End(0)).await?,
+        };
+
+        Ok(Self {
+            reader: BufReader::new(f),
+            offset,
+            partial: Vec::new(),
+        })
+    }
+
+    /// `None` when there are no more complete lines for now
+    pub async fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            if self.reader.read_until(b'\n', &mut self.partial).await? == 0 {
+                return Ok(None);
+            }
+
+            if self.partial.ends_with(b"\n") {
+                self.offset += self.partial.len() as u64;
+                let line = String::from_utf8_lossy(&self.partial)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string();
+                self.partial.clear();
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    /// the unterminated last line, once the file is known to be finished
+    pub fn take_partial_line(&mut self) -> Option<String> {
+        if self.partial.is_empty() {
+            return None;
+        }
+
+        self.offset += self.partial.len() as u64;
+        let line = String::from_utf8_lossy(&self.partial).to_string();
+        self.partial.clear();
+        Some(line)
+    }
+
+    /// byte offset after the last line returned
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::LogReader;
+
+    #[tokio::test]
+    async fn reads_complete_lines_and_tracks_offset() {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        write!(f, "first\r\nsecond\nthi").unwrap();
+
+        let mut reader = LogReader::open(f.path(), Some(0)).await.unwrap();
+        assert_eq!(reader.next_line().await.unwrap().as_deref(), Some("first"));
+        assert_eq!(reader.offset(), 7);
+        assert_eq!(reader.next_line().await.unwrap().as_deref(), Some("second"));
+        assert_eq!(reader.offset(), 14);
+        assert_eq!(reader.next_line().await.unwrap(), None);
+        assert_eq!(reader.offset(), 14);
+
+        writeln!(f, "rd").unwrap();
+        assert_eq!(reader.next_line().await.unwrap().as_deref(), Some("third"));
+        assert_eq!(reader.offset(), 20);
+
+        let mut reader = LogReader::open(f.path(), Some(7)).await.unwrap();
+        assert_eq!(reader.next_line().await.unwrap().as_deref(), Some("second"));
+
+        let mut reader = LogReader::open(f.path(), None).await.unwrap();
+        assert_eq!(reader.offset(), 20);
+        assert_eq!(reader.next_line().await.unwrap(), None);
+    }
+}
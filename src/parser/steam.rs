@@ -0,0 +1,144 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use tokio::fs;
+
+const VRCHAT_APP_ID: &str = "438100";
+
+/// the log directory inside a Proton prefix
+const PREFIX_VRCHAT_DIR: &str = "pfx/drive_c/users/steamuser/AppData/LocalLow/VRChat/VRChat";
+
+/// Native, Flatpak and Snap Steam installs
+fn steam_roots(home_dir: &Path) -> Vec<PathBuf> {
+    [
+        ".steam/steam",
+        ".steam/root",
+        ".local/share/Steam",
+        ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+        ".var/app/com.valvesoftware.Steam/data/Steam",
+        "snap/steam/common/.local/share/Steam",
+    ]
+    .into_iter()
+    .map(|path| home_dir.join(path))
+    .collect()
+}
+
+/// Where the log directory could be, most likely first. Doesn't check they
+/// exist.
+pub async fn candidate_vrchat_dirs(home_dir: &Path) -> Vec<PathBuf> {
+    let mut prefixes = Vec::new();
+
+    // set when started from Steam, the prefix of whatever it launched
+    if let Some(compat_data_path) = env::var_os("STEAM_COMPAT_DATA_PATH") {
+        prefixes.push(PathBuf::from(compat_data_path));
+    }
+
+    for steam_root in steam_roots(home_dir) {
+        if !steam_root.is_dir() {
+            continue;
+        }
+
+        let mut libraries = vec![steam_root.clone()];
+        for vdf_path in [
+            steam_root.join("steamapps/libraryfolders.vdf"),
+            steam_root.join("config/libraryfolders.vdf"),
+        ] {
+            if let Ok(text) = fs::read_to_string(&vdf_path).await {
+                libraries.extend(parse_library_folders(&text));
+            }
+        }
+
+        prefixes.extend(
+            libraries
+                .into_iter()
+                .map(|library| library.join("steamapps/compatdata").join(VRCHAT_APP_ID)),
+        );
+    }
+
+    let mut vrchat_dirs = Vec::new();
+    for prefix in prefixes {
+        let vrchat_dir = prefix.join(PREFIX_VRCHAT_DIR);
+        if !vrchat_dirs.contains(&vrchat_dir) {
+            vrchat_dirs.push(vrchat_dir);
+        }
+    }
+    vrchat_dirs
+}
+
+/// The `"path"` of every library in Steam's `libraryfolders.vdf`
+#[must_use]
+pub fn parse_library_folders(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .filter_map(|line| match quoted_strings(line).as_slice() {
+            [key, value] if key.eq_ignore_ascii_case("path") => Some(PathBuf::from(value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// the `"quoted"` strings in a line, with backslash escapes
+fn quoted_strings(line: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => string.extend(chars.next()),
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::parse_library_folders;
+
+    #[test]
+    fn parses_library_folders() {
+        let text = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"label"		""
+		"apps"
+		{
+			"438100"		"123456"
+		}
+	}
+	"1"
+	{
+		"path"		"/mnt/games/Steam \"Library\""
+	}
+	"2"
+	{
+		"path"		"D:\\SteamLibrary"
+	}
+}
+"#;
+        assert_eq!(
+            parse_library_folders(text),
+            vec![
+                PathBuf::from("/home/user/.local/share/Steam"),
+                PathBuf::from("/mnt/games/Steam \"Library\""),
+                PathBuf::from("D:\\SteamLibrary"),
+            ]
+        );
+    }
+}
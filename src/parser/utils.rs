@@ -6,42 +6,78 @@ use std::{
 use anyhow::{bail, Context, Result};
 use tokio::fs;
 
+#[cfg(target_os = "linux")]
+use super::steam::candidate_vrchat_dirs;
+use crate::config::settings::current_config;
+
+/// what to do when the log directory isn't where we looked
+const CONFIGURE_HINT: &str =
+    "set vrchat_dir in the config, or use --log-dir or the VRCHAT_LOG_DIR environment variable";
+
+/// `vrchat_dir` from the config or command line if set, otherwise found
+/// automatically
 pub async fn get_vrchat_dir() -> Result<PathBuf> {
-    let vrchat_dir = {
-        #[cfg(target_os = "windows")]
-        {
-            let mut cache_dir = dirs::cache_dir().context("cache_dir None")?;
-            if !cache_dir.pop() {
-                bail!("cache_dir.pop() false");
-            }
-            cache_dir.join("LocalLow\\VRChat\\VRChat")
-        }
+    let Some(vrchat_dir) = current_config().vrchat_dir.clone() else {
+        return find_vrchat_dir().await;
+    };
 
-        #[cfg(target_os = "linux")]
-        {
-            let home_dir = dirs::home_dir().context("home_dir None")?;
-            home_dir
-                .join(
-                    ".steam/steam/steamapps/compatdata/438100/pfx/drive_c/users/steamuser/AppData",
-                )
-                .join("LocalLow/VRChat/VRChat")
-        }
+    if !is_dir(&vrchat_dir).await {
+        bail!("vrchat dir {vrchat_dir:?} doesn't exist");
+    }
 
-        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-        {
-            compile_error!("TODO");
+    Ok(vrchat_dir)
+}
+
+#[cfg(target_os = "windows")]
+async fn find_vrchat_dir() -> Result<PathBuf> {
+    let mut cache_dir = dirs::cache_dir().context("cache_dir None")?;
+    if !cache_dir.pop() {
+        bail!("cache_dir.pop() false");
+    }
+    let vrchat_dir = cache_dir.join("LocalLow\\VRChat\\VRChat");
+
+    if !is_dir(&vrchat_dir).await {
+        bail!("vrchat dir {vrchat_dir:?} doesn't exist, {CONFIGURE_HINT}");
+    }
+
+    Ok(vrchat_dir)
+}
+
+/// in the Proton prefix, in whichever Steam library the game is installed
+#[cfg(target_os = "linux")]
+async fn find_vrchat_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir().context("home_dir None")?;
+    let candidates = candidate_vrchat_dirs(&home_dir).await;
+
+    for vrchat_dir in &candidates {
+        if is_dir(vrchat_dir).await {
+            return Ok(vrchat_dir.clone());
         }
-    };
+    }
 
-    if !fs::metadata(&vrchat_dir)
+    if candidates.is_empty() {
+        bail!("couldn't find a Steam install, {CONFIGURE_HINT}");
+    }
+    bail!(
+        "couldn't find the vrchat dir in any of:\n  {}\n{CONFIGURE_HINT}",
+        candidates
+            .iter()
+            .map(|vrchat_dir| vrchat_dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join("\n  ")
+    );
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+async fn find_vrchat_dir() -> Result<PathBuf> {
+    bail!("don't know where VRChat's logs are on this OS, {CONFIGURE_HINT}");
+}
+
+async fn is_dir(path: &Path) -> bool {
+    fs::metadata(path)
         .await
         .map(|metadata| metadata.is_dir())
         .unwrap_or(false)
-    {
-        bail!("vrchat dir doesn't exist");
-    }
-
-    Ok(vrchat_dir)
 }
 
 pub const LOG_FILE_PREFIX: &str = "output_log_";
@@ -64,12 +100,10 @@ async fn is_log_file(path: &Path) -> bool {
     }
 }
 
-async fn get_log_paths_with_modified() -> Result<Vec<(PathBuf, SystemTime)>> {
-    let vrchat_dir = get_vrchat_dir().await?;
-
+async fn get_log_paths_with_modified(log_dir: &Path) -> Result<Vec<(PathBuf, SystemTime)>> {
     let mut log_paths_with_modified = Vec::new();
 
-    let mut entries = fs::read_dir(vrchat_dir).await?;
+    let mut entries = fs::read_dir(log_dir).await?;
     while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if !is_log_file(&path).await {
@@ -89,7 +123,11 @@ async fn get_log_paths_with_modified() -> Result<Vec<(PathBuf, SystemTime)>> {
 }
 
 pub async fn get_newest_log_path() -> Result<Option<PathBuf>> {
-    let mut paths = get_log_paths_with_modified().await?;
+    get_newest_log_path_in(&get_vrchat_dir().await?).await
+}
+
+pub async fn get_newest_log_path_in(log_dir: &Path) -> Result<Option<PathBuf>> {
+    let mut paths = get_log_paths_with_modified(log_dir).await?;
     paths.sort_by(|(_, a), (_, b)| b.cmp(a));
     let path = paths.into_iter().map(|(path, _)| path).next();
 
@@ -0,0 +1,162 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{ensure, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessType {
+    Public,
+    Friends,
+    FriendsPlus,
+    Invite,
+    InvitePlus,
+    Group,
+}
+
+impl Display for AccessType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Public => "public",
+            Self::Friends => "friends",
+            Self::FriendsPlus => "friends+",
+            Self::Invite => "invite",
+            Self::InvitePlus => "invite+",
+            Self::Group => "group",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// `wrld_...:12345~hidden(usr_...)~region(eu)`
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct WorldInstance {
+    /// `wrld_...`
+    pub world_id: String,
+    /// everything after the `:`, `12345~hidden(usr_...)~region(eu)`
+    pub instance_id: String,
+    pub access_type: AccessType,
+    /// `us`, `use`, `eu`, `jp`
+    pub region: String,
+    /// `usr_...` or `grp_...`, missing for public instances
+    pub owner: Option<String>,
+    /// from the `Entering Room` line that follows
+    pub world_name: Option<String>,
+}
+
+impl FromStr for WorldInstance {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (world_id, instance_id) = s.split_once(':').context("no instance id")?;
+        ensure!(world_id.starts_with("wrld_"), "bad world id {world_id:?}");
+
+        let mut access_type = AccessType::Public;
+        let mut region = "us".to_string();
+        let mut owner = None;
+
+        // 12345~private(usr_...)~canRequestInvite~region(eu)~nonce(...)
+        for tag in instance_id.split('~').skip(1) {
+            let (name, value) = tag
+                .strip_suffix(')')
+                .and_then(|tag| tag.split_once('('))
+                .unwrap_or((tag, ""));
+
+            match name {
+                "hidden" => {
+                    access_type = AccessType::FriendsPlus;
+                    owner = Some(value.to_string());
+                }
+                "friends" => {
+                    access_type = AccessType::Friends;
+                    owner = Some(value.to_string());
+                }
+                "private" => {
+                    access_type = AccessType::Invite;
+                    owner = Some(value.to_string());
+                }
+                "canRequestInvite" => {
+                    access_type = AccessType::InvitePlus;
+                }
+                "group" => {
+                    access_type = AccessType::Group;
+                    owner = Some(value.to_string());
+                }
+                "region" => {
+                    region = value.to_string();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            world_id: world_id.to_string(),
+            instance_id: instance_id.to_string(),
+            access_type,
+            region,
+            owner,
+            world_name: None,
+        })
+    }
+}
+
+impl Display for WorldInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.world_name.as_ref().unwrap_or(&self.world_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessType, WorldInstance};
+
+    fn parse(s: &str) -> (AccessType, String, Option<String>) {
+        let instance: WorldInstance = s.parse().unwrap();
+        (instance.access_type, instance.region, instance.owner)
+    }
+
+    #[test]
+    fn parses_access_types() {
+        let owner = || Some("usr_o".to_string());
+
+        assert_eq!(
+            parse("wrld_a:12345"),
+            (AccessType::Public, "us".to_string(), None)
+        );
+        assert_eq!(
+            parse("wrld_a:12345~region(jp)"),
+            (AccessType::Public, "jp".to_string(), None)
+        );
+        assert_eq!(
+            parse("wrld_a:12345~friends(usr_o)~region(eu)"),
+            (AccessType::Friends, "eu".to_string(), owner())
+        );
+        assert_eq!(
+            parse("wrld_a:12345~hidden(usr_o)~region(eu)"),
+            (AccessType::FriendsPlus, "eu".to_string(), owner())
+        );
+        assert_eq!(
+            parse("wrld_a:12345~private(usr_o)~region(use)~nonce(abc)"),
+            (AccessType::Invite, "use".to_string(), owner())
+        );
+        assert_eq!(
+            parse("wrld_a:12345~private(usr_o)~canRequestInvite~region(us)"),
+            (AccessType::InvitePlus, "us".to_string(), owner())
+        );
+        assert_eq!(
+            parse("wrld_a:12345~group(grp_g)~groupAccessType(members)~region(eu)"),
+            (
+                AccessType::Group,
+                "eu".to_string(),
+                Some("grp_g".to_string())
+            )
+        );
+
+        let instance: WorldInstance = "wrld_a:12345~region(eu)".parse().unwrap();
+        assert_eq!(instance.world_id, "wrld_a");
+        assert_eq!(instance.instance_id, "12345~region(eu)");
+
+        assert!("wrld_a".parse::<WorldInstance>().is_err());
+        assert!("usr_a:12345".parse::<WorldInstance>().is_err());
+    }
+}
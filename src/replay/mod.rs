@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+
+use crate::{
+    notifier::sinks::{register_sink, stdout::StdoutSink, SinkFilter},
+    parser::LogParser,
+};
+
+/// Run an existing log through the parser, printing the notifications it
+/// would have sent.
+///
+/// `speed` scales the original timing, `None` runs at full speed.
+pub async fn replay(log_path: &Path, speed: Option<f64>) -> Result<()> {
+    if let Some(speed) = speed {
+        ensure!(
+            speed.is_finite() && speed > 0.0,
+            "speed must be a positive number"
+        );
+    }
+
+    // there's no vr runtime to show these
+    register_sink("stdout", StdoutSink, SinkFilter::default());
+
+    println!("replaying {}", log_path.display());
+    let parser = LogParser::new(log_path, None)?;
+    parser.replay(speed).await?;
+
+    Ok(())
+}
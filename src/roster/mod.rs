@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+
+use crate::parser::{event::Player, world::WorldInstance};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub player: Player,
+    pub joined_at: PrimitiveDateTime,
+}
+
+/// Players currently in our instance, keyed by `Player::key`. Each log
+/// source has its own.
+#[derive(Debug, Clone, Default)]
+pub struct Roster {
+    world: Option<WorldInstance>,
+    entries: HashMap<String, RosterEntry>,
+}
+
+impl Roster {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn world(&self) -> Option<&WorldInstance> {
+        self.world.as_ref()
+    }
+
+    pub fn set_world(&mut self, world: Option<WorldInstance>) {
+        self.world = world;
+    }
+
+    pub fn set_world_name(&mut self, world_name: &str) {
+        if let Some(world) = &mut self.world {
+            world.world_name = Some(world_name.to_string());
+        }
+    }
+
+    /// returns false if the player was already here
+    pub fn join(&mut self, player: Player, joined_at: PrimitiveDateTime) -> bool {
+        let key = player.key().to_string();
+        if self.entries.contains_key(&key) {
+            return false;
+        }
+
+        self.entries.insert(key, RosterEntry { player, joined_at });
+        true
+    }
+
+    pub fn leave(&mut self, player: &Player) -> Option<RosterEntry> {
+        self.entries.remove(player.key())
+    }
+
+    /// forget all players, keeping the world
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// ordered by join time, oldest first
+    #[must_use]
+    pub fn entries(&self) -> Vec<&RosterEntry> {
+        let mut entries = self.entries.values().collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            a.joined_at
+                .cmp(&b.joined_at)
+                .then_with(|| a.player.display_name.cmp(&b.player.display_name))
+        });
+        entries
+    }
+}
+
+pub type SharedRoster = Arc<Mutex<Roster>>;
+
+/// every log source's roster, `None` for the main one
+static ROSTERS: LazyLock<Mutex<HashMap<Option<String>, SharedRoster>>> =
+    LazyLock::new(Mutex::default);
+
+/// The roster of the log source named `source`, shared with its parser.
+/// Created empty the first time it's asked for.
+#[must_use]
+pub fn roster_handle(source: Option<&str>) -> SharedRoster {
+    let mut rosters = ROSTERS.lock().unwrap();
+    rosters
+        .entry(source.map(ToString::to_string))
+        .or_default()
+        .clone()
+}
+
+pub fn with_roster<F, R>(source: Option<&str>, f: F) -> R
+where
+    F: FnOnce(&mut Roster) -> R,
+{
+    let roster = roster_handle(source);
+    let mut roster = roster.lock().unwrap();
+    f(&mut roster)
+}
+
+/// snapshot of who is in `source`'s instance right now
+#[must_use]
+pub fn current_roster(source: Option<&str>) -> Roster {
+    with_roster(source, |roster| roster.clone())
+}
+
+#[must_use]
+pub fn current_world(source: Option<&str>) -> Option<WorldInstance> {
+    with_roster(source, |roster| roster.world().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{current_roster, current_world, roster_handle, Roster};
+    use crate::parser::event::Player;
+
+    fn names(roster: &Roster) -> Vec<&str> {
+        roster
+            .entries()
+            .into_iter()
+            .map(|entry| entry.player.display_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn tracks_joins_and_leaves() {
+        let mut roster = Roster::new();
+        assert!(roster.is_empty());
+
+        assert!(roster.join(Player::parse("Bob (usr_b)"), datetime!(2024-01-01 20:05:00)));
+        assert!(roster.join(
+            Player::parse("Carol (usr_c)"),
+            datetime!(2024-01-01 20:00:00)
+        ));
+        // same user id under a new name is still the same player
+        assert!(!roster.join(
+            Player::parse("Robert (usr_b)"),
+            datetime!(2024-01-01 20:10:00)
+        ));
+        assert!(roster.join(Player::parse("Alice"), datetime!(2024-01-01 20:05:00)));
+
+        // oldest first, then by name
+        assert_eq!(names(&roster), vec!["Carol", "Alice", "Bob"]);
+
+        let left = roster.leave(&Player::parse("Bob (usr_b)")).unwrap();
+        assert_eq!(left.joined_at, datetime!(2024-01-01 20:05:00));
+        assert_eq!(roster.leave(&Player::parse("Bob (usr_b)")), None);
+        assert_eq!(roster.len(), 2);
+    }
+
+    #[test]
+    fn clear_keeps_the_world() {
+        let mut roster = Roster::new();
+        roster.set_world(Some("wrld_a:1~region(eu)".parse().unwrap()));
+        roster.set_world_name("Test World");
+        roster.join(Player::parse("Bob (usr_b)"), datetime!(2024-01-01 20:00:00));
+
+        roster.clear();
+        assert!(roster.is_empty());
+        assert_eq!(roster.world().unwrap().to_string(), "Test World");
+
+        roster.set_world(None);
+        assert_eq!(roster.world(), None);
+        // nothing to name
+        roster.set_world_name("Other World");
+        assert_eq!(roster.world(), None);
+    }
+
+    #[test]
+    fn keeps_a_roster_per_source() {
+        let first = roster_handle(Some("roster-first"));
+        first
+            .lock()
+            .unwrap()
+            .set_world(Some("wrld_a:1".parse().unwrap()));
+        first
+            .lock()
+            .unwrap()
+            .join(Player::parse("Bob (usr_b)"), datetime!(2024-01-01 20:00:00));
+
+        let roster = current_roster(Some("roster-first"));
+        assert_eq!(roster.len(), 1);
+        assert_eq!(
+            roster.entries()[0].joined_at,
+            datetime!(2024-01-01 20:00:00)
+        );
+        assert_eq!(
+            current_world(Some("roster-first")).map(|world| world.world_id),
+            Some("wrld_a".to_string())
+        );
+
+        assert!(current_roster(Some("roster-second")).is_empty());
+        assert_eq!(current_world(Some("roster-second")), None);
+    }
+}
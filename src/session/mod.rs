@@ -0,0 +1,228 @@
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::{macros::format_description, Duration, PrimitiveDateTime};
+
+use crate::{
+    config::settings::current_config,
+    notifier::{send_notification, Notification, NotificationKind},
+    parser::{event::Player, world::WorldInstance},
+};
+
+/// What happens to the summary when we leave an instance
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SummaryOptions {
+    /// printed to the terminal
+    pub enabled: bool,
+    /// also appended to this file
+    pub file: Option<PathBuf>,
+    /// also sent as a notification of kind `summary`, which the other sinks
+    /// get too unless their `kinds` leave it out
+    pub toast: bool,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file: None,
+            toast: false,
+        }
+    }
+}
+
+/// One visit to an instance, from finishing loading until leaving
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Session {
+    world: Option<WorldInstance>,
+    started_at: PrimitiveDateTime,
+    /// most players in the instance at once, including us
+    peak: usize,
+    /// in order, not counting who was there when we arrived
+    joined: Vec<Player>,
+    left: Vec<Player>,
+}
+
+impl Session {
+    #[must_use]
+    pub fn new(world: Option<WorldInstance>, started_at: PrimitiveDateTime, count: usize) -> Self {
+        Self {
+            world,
+            started_at,
+            peak: count,
+            joined: Vec::new(),
+            left: Vec::new(),
+        }
+    }
+
+    /// `count` is the number of players after they joined
+    pub fn join(&mut self, player: &Player, count: usize) {
+        self.peak = self.peak.max(count);
+        if !self.joined.iter().any(|other| other.key() == player.key()) {
+            self.joined.push(player.clone());
+        }
+    }
+
+    pub fn leave(&mut self, player: &Player) {
+        if !self.left.iter().any(|other| other.key() == player.key()) {
+            self.left.push(player.clone());
+        }
+    }
+
+    /// Print the summary and send it wherever it's configured to go, tagged
+    /// with the log `source` if it isn't the main one.
+    ///
+    /// No toast while `catching_up`, that visit is long over.
+    pub fn finish(self, ended_at: PrimitiveDateTime, catching_up: bool, source: Option<&str>) {
+        let options = current_config().summary.clone();
+        if !options.enabled {
+            return;
+        }
+
+        let mut text = self.text(ended_at);
+        if let Some(source) = source {
+            text.insert_str(0, &format!("[{source}] "));
+        }
+        println!("{text}");
+
+        if let Some(path) = &options.file {
+            if let Err(e) = append(path, &text) {
+                eprintln!("session summary: {e:?}");
+            }
+        }
+
+        if options.toast && !catching_up {
+            let max_names = current_config().grouping.max_names;
+            send_notification(&Notification {
+                timestamp: ended_at,
+                body: self.body(Some(max_names)),
+                world: self.world.clone(),
+                source: source.map(ToString::to_string),
+                ..Notification::new(NotificationKind::Summary, &self.title(ended_at))
+            });
+        }
+    }
+
+    fn title(&self, ended_at: PrimitiveDateTime) -> String {
+        format!(
+            "Left {} after {}",
+            self.world
+                .as_ref()
+                .map_or_else(|| "instance".to_string(), ToString::to_string),
+            format_duration(ended_at - self.started_at)
+        )
+    }
+
+    /// `max_names` truncates the name lists
+    fn body(&self, max_names: Option<usize>) -> Vec<String> {
+        let mut body = vec![format!(
+            "peak {} player{}",
+            self.peak,
+            if self.peak == 1 { "" } else { "s" }
+        )];
+        for (verb, players) in [("joined", &self.joined), ("left", &self.left)] {
+            if !players.is_empty() {
+                body.push(format!(
+                    "{} {verb}: {}",
+                    players.len(),
+                    names(players, max_names)
+                ));
+            }
+        }
+        body
+    }
+
+    /// full summary for the terminal and file
+    fn text(&self, ended_at: PrimitiveDateTime) -> String {
+        let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+        let mut text = self.title(ended_at);
+        if let Some(world) = &self.world {
+            let _ = write!(text, "\n    {}:{}", world.world_id, world.instance_id);
+        }
+        let _ = write!(
+            text,
+            "\n    {} to {}",
+            self.started_at.format(&format).unwrap_or_default(),
+            ended_at.format(&format).unwrap_or_default()
+        );
+        for line in self.body(None) {
+            let _ = write!(text, "\n    {line}");
+        }
+        text
+    }
+}
+
+fn names(players: &[Player], max_names: Option<usize>) -> String {
+    let max_names = max_names.unwrap_or(usize::MAX);
+    let mut names = players
+        .iter()
+        .take(max_names)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if players.len() > max_names {
+        let _ = write!(names, " and {} more", players.len() - max_names);
+    }
+    names
+}
+
+/// `1h 23m`, `5m`, `<1m`
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.whole_minutes();
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "<1m".to_string(),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+fn append(path: &Path, text: &str) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening {path:?}"))?;
+    writeln!(f, "{text}\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::Session;
+    use crate::parser::{event::Player, world::WorldInstance};
+
+    #[test]
+    fn summarizes_visit() {
+        let mut world = "wrld_1:12345".parse::<WorldInstance>().unwrap();
+        world.world_name = Some("Test World".to_string());
+
+        let mut session = Session::new(Some(world), datetime!(2024-01-01 20:00:00), 2);
+        let bob = Player::parse("Bob (usr_b)");
+        session.join(&bob, 3);
+        session.join(&Player::parse("Carol (usr_c)"), 4);
+        session.leave(&bob);
+        session.join(&bob, 4);
+
+        let ended_at = datetime!(2024-01-01 21:23:00);
+        assert_eq!(session.title(ended_at), "Left Test World after 1h 23m");
+        assert_eq!(
+            session.text(ended_at),
+            "Left Test World after 1h 23m
+    wrld_1:12345
+    2024-01-01 20:00:00 to 2024-01-01 21:23:00
+    peak 4 players
+    2 joined: Bob, Carol
+    1 left: Bob"
+        );
+        assert_eq!(session.body(Some(1))[1], "2 joined: Bob and 1 more");
+    }
+}
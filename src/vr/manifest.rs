@@ -20,7 +20,11 @@ pub enum VrManifestSource {
 pub struct VrManifestApplication {
     app_key: String,
     launch_type: VrManifestApplicationLaunchType,
-    binary_path_windows: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary_path_windows: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary_path_linux: Option<String>,
+    arguments: String,
     is_dashboard_overlay: bool,
     strings: HashMap<String, VrManifestApplicationString>,
 }
@@ -37,17 +41,42 @@ pub struct VrManifestApplicationString {
     description: String,
 }
 
+/// Which binary path the manifest sets, `SteamVR` ignores the others
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Platform {
+    Windows,
+    Linux,
+}
+
+impl Platform {
+    #[must_use]
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else {
+            Self::Linux
+        }
+    }
+}
+
 impl VrManifest {
+    /// `binary_path` is relative to the manifest
     #[must_use]
-    pub fn new(binary_path_windows: &str) -> Self {
+    pub fn new(platform: Platform, binary_path: &str) -> Self {
         let app_key = APP_KEY.into();
-        let binary_path_windows = binary_path_windows.into();
+        let (binary_path_windows, binary_path_linux) = match platform {
+            Platform::Windows => (Some(binary_path.into()), None),
+            Platform::Linux => (None, Some(binary_path.into())),
+        };
 
         Self {
             source: VrManifestSource::Builtin,
             applications: vec![VrManifestApplication {
                 app_key,
                 binary_path_windows,
+                binary_path_linux,
+                // what running without a subcommand does anyway
+                arguments: "run".into(),
                 is_dashboard_overlay: true,
                 launch_type: VrManifestApplicationLaunchType::Binary,
                 strings: HashMap::from([(
@@ -61,3 +90,60 @@ impl VrManifest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Platform, VrManifest};
+
+    #[test]
+    fn windows_manifest_json() {
+        let manifest = VrManifest::new(Platform::Windows, "vrchat-join-leave-notifier.exe");
+        assert_eq!(
+            serde_json::to_string_pretty(&manifest).unwrap(),
+            r#"{
+  "source": "builtin",
+  "applications": [
+    {
+      "app_key": "vrchat-join-leave-notifier",
+      "launch_type": "binary",
+      "binary_path_windows": "vrchat-join-leave-notifier.exe",
+      "arguments": "run",
+      "is_dashboard_overlay": true,
+      "strings": {
+        "en_us": {
+          "name": "VRChat Join/Leave Notifier",
+          "description": "VRChat/Join Leave Notifier"
+        }
+      }
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn linux_manifest_json() {
+        let manifest = VrManifest::new(Platform::Linux, "vrchat-join-leave-notifier");
+        assert_eq!(
+            serde_json::to_string_pretty(&manifest).unwrap(),
+            r#"{
+  "source": "builtin",
+  "applications": [
+    {
+      "app_key": "vrchat-join-leave-notifier",
+      "launch_type": "binary",
+      "binary_path_linux": "vrchat-join-leave-notifier",
+      "arguments": "run",
+      "is_dashboard_overlay": true,
+      "strings": {
+        "en_us": {
+          "name": "VRChat Join/Leave Notifier",
+          "description": "VRChat/Join Leave Notifier"
+        }
+      }
+    }
+  ]
+}"#
+        );
+    }
+}
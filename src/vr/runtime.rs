@@ -1,6 +1,7 @@
 use std::{thread, time::Duration};
 
-use anyhow::{ensure, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
+use deunicode::deunicode;
 use openvr::{
     sys::{EVRNotificationStyle_None, EVRNotificationType_Transient},
     system::Event,
@@ -11,44 +12,185 @@ use tokio::sync::{
     mpsc, oneshot,
 };
 
-use crate::notifier::{notify, NOTIFICATION};
+use crate::{
+    config::settings::current_config,
+    notifier::{
+        notify,
+        sinks::{
+            desktop::DesktopSink, register_configured_sinks, register_sink, unregister_sink,
+            NotificationSink, SinkConfig, SinkFilter, SinkType,
+        },
+        Notification,
+    },
+};
+
+/// only used while detached, unless a desktop sink is configured anyway
+const DESKTOP_FALLBACK_SINK_NAME: &str = "desktop fallback";
+
+/// Hands notifications to the openvr thread to show as toasts
+struct SteamVrSink {
+    sender: mpsc::Sender<String>,
+}
+
+impl NotificationSink for SteamVrSink {
+    fn send(&self, notification: &Notification) -> Result<()> {
+        // toasts show multiple lines fine
+        let mut text = notification.tagged_title();
+        for line in &notification.body {
+            text.push('\n');
+            text.push_str(line);
+        }
 
-pub async fn start_runtime(shutdown_send: broadcast::Sender<()>) -> Result<()> {
+        self.sender.try_send(deunicode(&text))?;
+        Ok(())
+    }
+}
+
+/// How often to check if `SteamVR` started when running without it
+const ATTACH_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// With `vr_optional`, keeps running without `SteamVR`, showing desktop
+/// notifications instead, and attaches whenever it's started.
+pub async fn start_runtime(shutdown_send: broadcast::Sender<()>, vr_optional: bool) -> Result<()> {
     let mut shutdown_recv = shutdown_send.subscribe();
 
-    let mut vr_event_receiver = start_event_stream(shutdown_send.clone()).await?;
+    if !vr_optional {
+        let mut vr_event_receiver = start_event_stream(shutdown_send.clone()).await?;
+
+        tokio::spawn(async move {
+            wait_for_quit(&mut shutdown_recv, &mut vr_event_receiver).await;
+
+            println!("start_runtime end");
+            let _ = shutdown_send.send(());
+        });
+
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(3)).await;
+            notify("init");
+        });
+
+        return Ok(());
+    }
 
     tokio::spawn(async move {
-        loop {
-            tokio::select! {
-                _ = shutdown_recv.recv() => {
-                    println!("start_runtime got shutdown");
-                    break;
-                },
-                option = vr_event_receiver.recv() => {
-                    if let Some(event) = option {
-                        if let Event::Quit(_) = event {
-                            break;
+        'outer: loop {
+            if current_config().sink(SinkType::Desktop).is_none() {
+                register_sink(
+                    DESKTOP_FALLBACK_SINK_NAME,
+                    DesktopSink,
+                    SinkFilter::default(),
+                );
+            }
+
+            let mut logged_error = false;
+            let mut vr_event_receiver = loop {
+                let result = if is_steamvr_running().await {
+                    start_event_stream(shutdown_send.clone()).await
+                } else {
+                    Err(anyhow!("SteamVR isn't running"))
+                };
+
+                match result {
+                    Ok(vr_event_receiver) => break vr_event_receiver,
+                    Err(e) => {
+                        if !logged_error {
+                            logged_error = true;
+                            println!("running without SteamVR: {e}");
                         }
-                    } else {
-                        break;
                     }
-                },
+                }
+
+                tokio::select! {
+                    _ = shutdown_recv.recv() => {
+                        println!("start_runtime got shutdown");
+                        break 'outer;
+                    },
+                    () = tokio::time::sleep(ATTACH_RETRY_INTERVAL) => {},
+                }
+            };
+
+            unregister_sink(DESKTOP_FALLBACK_SINK_NAME);
+            notify("attached to SteamVR");
+
+            if wait_for_quit(&mut shutdown_recv, &mut vr_event_receiver).await
+                == RuntimeExit::Shutdown
+            {
+                break;
             }
+            println!("SteamVR quit, waiting for it to start again");
         }
 
+        unregister_sink(DESKTOP_FALLBACK_SINK_NAME);
         println!("start_runtime end");
-        let _ = shutdown_send.send(());
     });
 
-    tokio::spawn(async {
-        tokio::time::sleep(Duration::from_secs(3)).await;
-        notify("init").await.unwrap();
-    });
+    Ok(())
+}
+
+/// Sends one notification to the configured sinks, as a toast too if
+/// `SteamVR` is running, and waits for it to be shown.
+pub async fn test_notify(text: &str) -> Result<()> {
+    let (shutdown_send, mut shutdown_recv) = broadcast::channel(8);
+
+    register_configured_sinks(&current_config().sinks);
+
+    // kept so the openvr thread doesn't stop on its first event
+    let _vr_event_receiver = if is_steamvr_running().await {
+        Some(start_event_stream(shutdown_send.clone()).await?)
+    } else {
+        println!("SteamVR isn't running, no toast");
+        None
+    };
+
+    notify(text);
+
+    // the openvr thread and some sinks send from their own threads
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    let _ = shutdown_send.send(());
+    drop(shutdown_send);
+    while shutdown_recv.recv().await.is_ok() {}
 
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum RuntimeExit {
+    Shutdown,
+    Quit,
+}
+
+async fn wait_for_quit(
+    shutdown_recv: &mut broadcast::Receiver<()>,
+    vr_event_receiver: &mut mpsc::Receiver<Event>,
+) -> RuntimeExit {
+    loop {
+        tokio::select! {
+            _ = shutdown_recv.recv() => {
+                println!("start_runtime got shutdown");
+                return RuntimeExit::Shutdown;
+            },
+            option = vr_event_receiver.recv() => {
+                match option {
+                    Some(Event::Quit(_)) | None => return RuntimeExit::Quit,
+                    Some(_) => {}
+                }
+            },
+        }
+    }
+}
+
+/// `Overlay` applications start `SteamVR` if it isn't running, `Background`
+/// ones don't
+async fn is_steamvr_running() -> bool {
+    tokio::task::spawn_blocking(|| {
+        unsafe { openvr::init(ApplicationType::Background) }
+            .map(|context| unsafe { context.shutdown() })
+            .is_ok()
+    })
+    .await
+    .unwrap_or(false)
+}
+
 async fn start_event_stream(shutdown_send: broadcast::Sender<()>) -> Result<mpsc::Receiver<Event>> {
     let mut shutdown_recv = shutdown_send.subscribe();
 
@@ -74,22 +216,27 @@ async fn start_event_stream(shutdown_send: broadcast::Sender<()>) -> Result<mpsc
         })();
 
         let (context, system, overlay, notifications, notifications_overlay) = match setup_result {
-            Ok(setup) => {
-                ok_send.send(true).unwrap();
-                setup
-            }
+            Ok(setup) => setup,
             Err(e) => {
-                eprintln!("start_event_stream: {e:?}");
-                ok_send.send(false).unwrap();
+                ok_send.send(Err(e)).unwrap();
                 return;
             }
         };
 
-        let mut notification_receiver = {
-            let guard = NOTIFICATION.lock().unwrap();
-            let mut cell = guard.borrow_mut();
-            cell.receiver.take().unwrap()
-        };
+        let (notification_sender, mut notification_receiver) = mpsc::channel(32);
+        // registered even when turned off in the config, so reloading it can
+        // turn toasts back on
+        register_sink(
+            &SinkType::Steamvr.to_string(),
+            SteamVrSink {
+                sender: notification_sender,
+            },
+            current_config()
+                .sink(SinkType::Steamvr)
+                .map_or_else(SinkFilter::nothing, SinkConfig::filter),
+        );
+        // only now, so a notification sent right after attaching isn't lost
+        ok_send.send(Ok(())).unwrap();
 
         let loop_result = (move || {
             'outer: loop {
@@ -145,15 +292,19 @@ async fn start_event_stream(shutdown_send: broadcast::Sender<()>) -> Result<mpsc
             anyhow::Ok(())
         })();
 
+        unregister_sink(&SinkType::Steamvr.to_string());
+
         if let Err(e) = loop_result {
             eprintln!("start_event_stream: {e:?}");
         }
         println!("start_event_stream end");
-        let _ = shutdown_send.send(());
+
+        // start_runtime decides whether this shuts everything down, main
+        // just waits for us to drop our sender
+        drop(shutdown_send);
     });
 
-    let ok = ok_recv.await?;
-    ensure!(ok, "start_event_stream failed");
+    ok_recv.await?.context("start_event_stream failed")?;
 
     Ok(vr_event_receiver)
 }
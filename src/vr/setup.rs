@@ -1,6 +1,11 @@
+use std::path::Path;
+
 use anyhow::{ensure, Context, Result};
 
-use crate::{config::setup_vr::setup_vr_files, vr::APP_KEY};
+use crate::{
+    config::setup_vr::{get_manifest_path, remove_vr_files, setup_vr_files},
+    vr::APP_KEY,
+};
 
 pub async fn setup_vr() -> Result<()> {
     let manifest_path = setup_vr_files().await?;
@@ -34,3 +39,68 @@ pub async fn setup_vr() -> Result<()> {
 
     Ok(())
 }
+
+/// Undoes `setup_vr`, printing what was removed. The files are deleted even
+/// if `SteamVR` can't be reached.
+pub async fn uninstall_vr() -> Result<()> {
+    let manifest_path = get_manifest_path().await?;
+
+    match unsafe { openvr::init(openvr::ApplicationType::Utility) } {
+        Ok(context) => {
+            let result = unregister(&context, &manifest_path);
+            unsafe { context.shutdown() };
+            result?;
+        }
+        Err(e) => println!("couldn't reach SteamVR, skipping unregister: {e}"),
+    }
+
+    for path in remove_vr_files().await? {
+        println!("deleted {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn unregister(context: &openvr::Context, manifest_path: &Path) -> Result<()> {
+    let applications = context.applications()?;
+
+    if !applications.is_application_installed(APP_KEY) {
+        println!("wasn't installed in SteamVR");
+        return Ok(());
+    }
+
+    if applications.get_application_auto_launch(APP_KEY) {
+        applications
+            .set_application_auto_launch(APP_KEY, false)
+            .context("set_application_auto_launch")?;
+        println!("turned off auto-launch");
+    }
+
+    applications
+        .remove_application_manifest(manifest_path)
+        .context("remove_application_manifest")?;
+
+    ensure!(
+        !applications.is_application_installed(APP_KEY),
+        "didn't uninstall"
+    );
+    println!("removed the manifest from SteamVR");
+
+    Ok(())
+}
+
+/// Whether `setup_vr` was run and auto-launch is still on
+pub fn check_installed() -> Result<()> {
+    let context = unsafe { openvr::init(openvr::ApplicationType::Utility) }
+        .context("connecting to SteamVR")?;
+    let applications = context.applications()?;
+
+    let installed = applications.is_application_installed(APP_KEY);
+    let auto_launch = applications.get_application_auto_launch(APP_KEY);
+    unsafe { context.shutdown() };
+
+    ensure!(installed, "not installed in SteamVR, run install");
+    ensure!(auto_launch, "auto-launch is off");
+
+    Ok(())
+}